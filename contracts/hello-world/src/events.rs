@@ -0,0 +1,213 @@
+use soroban_sdk::{contractevent, Address, BytesN, String, Vec};
+
+use crate::{EntropyMode, Role};
+
+// Emitted by create_round once the round record is stored
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundCreated {
+    #[topic]
+    pub round_id: u64,
+    pub token: Address,
+    pub ticket_price: i128,
+    pub initial_pool: i128,
+    pub end_time: u64,
+    pub reveal_deadline: u64,
+    pub allow_multiple: bool,
+}
+
+// Emitted by buy_ticket and buy_tickets for every ticket, after payment has been taken
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TicketPurchased {
+    #[topic]
+    pub round_id: u64,
+    #[topic]
+    pub buyer: Address,
+    pub ticket_index: u64,
+    pub amount: i128,
+}
+
+// Emitted by reveal_seed once the seed matches the ticket's stored commit
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SeedRevealed {
+    #[topic]
+    pub round_id: u64,
+    #[topic]
+    pub participant: Address,
+    pub ticket_index: u64,
+}
+
+// Emitted by finalize_round with every input to the round's final hash, so
+// anyone can recompute the draw; inputs the entropy mode does not use are None
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawEntropy {
+    #[topic]
+    pub round_id: u64,
+    pub mode: EntropyMode,
+    pub seed_hash: Option<BytesN<32>>,
+    pub ledger_sequence: Option<u32>,
+    pub prng_output: Option<BytesN<32>>,
+    pub final_hash: BytesN<32>,
+}
+
+// Emitted by finalize_round once per prize tier, with the inputs needed to audit the draw
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct WinnerSelected {
+    #[topic]
+    pub round_id: u64,
+    pub tier: u32,
+    pub winner: Address,
+    pub winner_index: u64,
+    pub reveal_count: u64,
+    pub entropy_hash: BytesN<32>,
+}
+
+// Emitted when a winner claims the prize tiers they won. fee_amount is the
+// protocol fee taken from those tiers, the round's fee split like its prizes,
+// so the claims of a round add up to its fee_amount.
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizePaid {
+    #[topic]
+    pub round_id: u64,
+    #[topic]
+    pub winner: Address,
+    pub tiers: Vec<u32>,
+    pub amount: i128,
+    pub fee_amount: i128,
+}
+
+// Emitted when a round is closed without a draw and refunds open up
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundCancelled {
+    #[topic]
+    pub round_id: u64,
+    pub participants_count: u64,
+}
+
+// Emitted when a buyer takes back their tickets from a cancelled round
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RefundClaimed {
+    #[topic]
+    pub round_id: u64,
+    #[topic]
+    pub participant: Address,
+    pub amount: i128,
+}
+
+// Emitted when unclaimed prizes are moved to the next round's pool
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PrizeExpired {
+    #[topic]
+    pub round_id: u64,
+    pub amount: i128,
+}
+
+// Emitted when the admin proposes a successor
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminProposed {
+    pub new_admin: Address,
+}
+
+// Emitted when a proposed admin accepts and takes over
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AdminChanged {
+    pub previous_admin: Address,
+    pub new_admin: Address,
+}
+
+// Emitted when the admin grants a role
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleGranted {
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+}
+
+// Emitted when the admin revokes a role
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoleRevoked {
+    pub role: Role,
+    #[topic]
+    pub account: Address,
+}
+
+// Emitted when the caller of finalize_round is paid the keeper reward
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct KeeperRewarded {
+    #[topic]
+    pub round_id: u64,
+    #[topic]
+    pub keeper: Address,
+    pub amount: i128,
+}
+
+// Emitted once by finalize_round when the round's protocol fee is set aside
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeeCollected {
+    #[topic]
+    pub round_id: u64,
+    pub token: Address,
+    pub amount: i128,
+}
+
+// Emitted when the treasury claims the protocol fees taken in a token
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FeesClaimed {
+    #[topic]
+    pub treasury: Address,
+    #[topic]
+    pub token: Address,
+    pub amount: i128,
+}
+
+// Emitted when ticket sales and new rounds are halted
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Paused {
+    pub reason: String,
+    pub by: Address,
+}
+
+// Emitted when ticket sales and new rounds resume
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Unpaused {
+    pub by: Address,
+}
+
+// Emitted when finalize_round asks the randomness provider for a Provider round's value
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RandomnessRequested {
+    #[topic]
+    pub round_id: u64,
+    pub provider: Address,
+    pub request_id: BytesN<32>,
+}
+
+// Emitted when the provider's signature for a round has been verified, with
+// the randomness derived from it, before the draw
+#[contractevent]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RandomnessFulfilled {
+    #[topic]
+    pub round_id: u64,
+    pub request_id: BytesN<32>,
+    pub randomness: BytesN<32>, // sha256(signature)
+    pub signature: BytesN<64>,
+}
@@ -0,0 +1,75 @@
+use soroban_sdk::{contracttype, Address, Vec};
+
+use crate::{CommitScheme, EntropyMode, LotteryRound, RevealPolicy};
+
+// LotteryRound as stored under schema version 1, before rounds had an
+// entropy mode, a commit scheme or a keeper reward. Only used to read rounds
+// that migrate has not rewritten yet.
+#[contracttype(export = false)]
+#[derive(Clone)]
+pub struct LotteryRoundV1 {
+    pub round_id: u64,
+    pub token: Address,
+    pub ticket_price: i128,
+    pub total_pool: i128,
+    pub participants_count: u64,
+    pub is_active: bool,
+    pub winners: Vec<Address>,
+    pub winning_tickets: Vec<u64>,
+    pub end_time: u64,
+    pub reveal_deadline: u64,
+    pub finalized: bool,
+    pub allow_multiple: bool,
+    pub min_participants: u64,
+    pub cancelled: bool,
+    pub prize_tiers: Vec<u32>,
+    pub prizes: Vec<i128>,
+    pub prizes_claimed: Vec<bool>,
+    pub prize_amount: i128,
+    pub claim_deadline: u64,
+    pub prize_expired: bool,
+    pub fee_bps: u32,
+    pub fee_amount: i128,
+    pub reveal_policy: RevealPolicy,
+    pub revealed_count: u64,
+    pub forfeited_bonds: i128,
+}
+
+impl LotteryRoundV1 {
+    // Version 1 rounds drew from revealed seeds and the ledger sequence,
+    // which is the CommitReveal mode, and their buyers committed under V1.
+    // That build paid the keeper reward in force at finalization, which is
+    // what keeper_bps is given.
+    pub fn into_current(self, keeper_bps: u32) -> LotteryRound {
+        LotteryRound {
+            round_id: self.round_id,
+            token: self.token,
+            ticket_price: self.ticket_price,
+            total_pool: self.total_pool,
+            participants_count: self.participants_count,
+            is_active: self.is_active,
+            winners: self.winners,
+            winning_tickets: self.winning_tickets,
+            end_time: self.end_time,
+            reveal_deadline: self.reveal_deadline,
+            finalized: self.finalized,
+            allow_multiple: self.allow_multiple,
+            min_participants: self.min_participants,
+            cancelled: self.cancelled,
+            prize_tiers: self.prize_tiers,
+            prizes: self.prizes,
+            prizes_claimed: self.prizes_claimed,
+            prize_amount: self.prize_amount,
+            claim_deadline: self.claim_deadline,
+            prize_expired: self.prize_expired,
+            fee_bps: self.fee_bps,
+            fee_amount: self.fee_amount,
+            keeper_bps,
+            reveal_policy: self.reveal_policy,
+            revealed_count: self.revealed_count,
+            forfeited_bonds: self.forfeited_bonds,
+            entropy_mode: EntropyMode::CommitReveal,
+            commit_scheme: CommitScheme::V1,
+        }
+    }
+}
@@ -1,34 +1,365 @@
 #![allow(non_snake_case)]
 #![no_std]
-use soroban_sdk::{contract, contracttype, contractimpl, log, Env, Address, Symbol, token, symbol_short, BytesN, Bytes};
+use soroban_sdk::{xdr::ToXdr, contract, contracterror, contracttype, contractimpl, log, Env, Address, String, Symbol, token, symbol_short, BytesN, Bytes, IntoVal, Map, TryFromVal, Val, Vec};
+
+mod events;
+mod legacy;
+mod provider;
+mod randomness;
+pub use events::{RoundCreated, TicketPurchased, SeedRevealed, WinnerSelected, PrizePaid, RoundCancelled, RefundClaimed, PrizeExpired, AdminProposed, AdminChanged, RoleGranted, RoleRevoked, KeeperRewarded, FeeCollected, FeesClaimed, Paused, Unpaused, DrawEntropy, RandomnessRequested, RandomnessFulfilled};
+pub use provider::{RandomnessProviderClient, RandomnessProviderInterface};
 
 // Structure to store lottery round details
 #[contracttype]
 #[derive(Clone)]
 pub struct LotteryRound {
     pub round_id: u64,
+    pub token: Address,
     pub ticket_price: i128,
     pub total_pool: i128,
     pub participants_count: u64,
     pub is_active: bool,
-    pub winner: Option<Address>,
+    pub winners: Vec<Address>,
+    pub winning_tickets: Vec<u64>,
     pub end_time: u64,
     pub reveal_deadline: u64,
     pub finalized: bool,
     pub allow_multiple: bool,
+    pub min_participants: u64,
+    pub cancelled: bool,
+    pub prize_tiers: Vec<u32>,
+    pub prizes: Vec<i128>,
+    pub prizes_claimed: Vec<bool>,
+    pub prize_amount: i128,
+    pub claim_deadline: u64,
+    pub prize_expired: bool,
+    pub fee_bps: u32,
+    pub fee_amount: i128,
+    pub keeper_bps: u32,
+    pub reveal_policy: RevealPolicy,
+    pub revealed_count: u64,
+    pub forfeited_bonds: i128,
+    pub entropy_mode: EntropyMode,
+    pub commit_scheme: CommitScheme,
+}
+
+// Parameters for a new round, passed to create_round
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RoundConfig {
+    pub token: Address, // must be on the admin's token allowlist
+    pub ticket_price: i128,
+    pub sale_duration: u64, // seconds tickets are on sale
+    pub reveal_duration: u64, // seconds seeds can be revealed once sales close
+    pub allow_multiple: bool,
+    pub min_participants: u64,
+    pub prize_tiers: Vec<u32>,
+    pub reveal_policy: RevealPolicy,
+    pub entropy_mode: EntropyMode,
+}
+
+// Bounds, in seconds, the admin allows for round sale and reveal durations
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DurationBounds {
+    pub min_sale_duration: u64,
+    pub max_sale_duration: u64,
+    pub min_reveal_duration: u64,
+    pub max_reveal_duration: u64,
+}
+
+// Duties the admin can delegate to other addresses. The admin holds every role.
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Role {
+    RoundOperator, // create_round
+    Finalizer, // finalize_round, except for Provider rounds, which anyone may finalize
+    FeeManager, // set_fee, set_treasury
+    Pauser, // pause, unpause
+}
+
+// Why and when sales were halted, kept while the contract is paused
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PauseState {
+    pub reason: String,
+    pub paused_by: Address,
+    pub paused_at: u64,
+}
+
+// Lifecycle phase of a round, computed from its flags and the ledger time
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RoundStatus {
+    Sale, // before end_time, tickets on sale
+    Reveal, // between end_time and reveal_deadline
+    Finalizable, // past reveal_deadline, waiting for finalize_round or cancel_round
+    Finalized,
+    Cancelled,
+}
+
+// A single ticket as returned by get_ticket
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ticket {
+    pub index: u64,
+    pub owner: Address,
+    pub commit: BytesN<32>,
+    pub seed: Option<Bytes>, // None until revealed
+}
+
+// Lifetime totals for one address, as returned by player_stats. Amounts are
+// kept per payment token since rounds can use different tokens.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PlayerStats {
+    pub rounds_played: u64,
+    pub tickets_bought: u64,
+    pub total_spent: Map<Address, i128>, // ticket prices paid, excluding bonds and before refunds
+    pub wins: u64, // prize tiers won
+    pub total_winnings: Map<Address, i128>, // prizes drawn, whether or not claimed
+}
+
+// One link of a round's seed chain, written by reveal_seed for every reveal
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RevealStep {
+    pub ticket_index: u64,
+    pub seed_hash: BytesN<32>, // chain of every seed revealed so far, this one included
+}
+
+// Everything finalize_round used to draw a round's winners, kept so the
+// draw can be checked on-chain with verify_draw or off-chain from get_draw
+// and the reveals listed by get_reveals
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawRecord {
+    pub entropy_mode: EntropyMode,
+    pub combined_hash: BytesN<32>, // the seed chain after the last reveal
+    pub ledger_sequence: Option<u32>, // None in Prng and Provider rounds
+    pub prng_output: Option<BytesN<32>>, // the provider's value in Provider rounds, None in CommitReveal rounds
+    pub final_hash: BytesN<32>,
+    pub reveal_count: u64,
+    pub eligible_count: u64, // tickets the draw picked from
+    pub revealed_only: bool, // slot k is the k-th revealed ticket rather than ticket k
+    pub winning_tickets: Vec<u64>, // one per prize tier, 1st place first
+}
+
+// Where a round's draw gets its randomness
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum EntropyMode {
+    CommitReveal, // revealed seeds, then the ledger sequence
+    CommitRevealPrng, // revealed seeds, the ledger sequence, then env.prng()
+    Prng, // env.prng() only; seeds are never revealed, for low-stakes rounds
+    Provider, // a signed value from the admin's randomness provider contract; seeds are never revealed
+}
+
+// External randomness source for Provider rounds, set by the admin
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RandomnessProvider {
+    pub contract: Address, // implements RandomnessProviderInterface
+    pub public_key: BytesN<32>, // ed25519 key the provider signs its values with
+}
+
+// A round's outstanding request to its randomness provider
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RandomnessRequest {
+    pub provider: RandomnessProvider, // the round's provider, which the request went to
+    pub request_id: BytesN<32>,
+    pub requested_by: Address, // caller of finalize_round, paid the keeper reward
+    pub ledger_sequence: u32,
+}
+
+// How a round's commit hashes are computed from the ticket's seed
+#[contracttype]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum CommitScheme {
+    // sha256(seed || participant strkey as UTF-8 || round_id), used by rounds
+    // created before schema version 2
+    V1,
+    // sha256(COMMIT_DOMAIN || contract address XDR || round_id || participant
+    // address XDR || seed), used by every new round
+    V2,
+}
+
+// How a round treats tickets whose seed is never revealed
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum RevealPolicy {
+    // Unrevealed tickets cannot win
+    Exclude,
+    // Every ticket posts this bond on top of the ticket price. It is returned
+    // on reveal and forfeited to the prize pool otherwise.
+    Bond(i128),
+}
+
+// Error codes returned by every fallible entrypoint. Soroban allows at most
+// 50 error cases and every one is taken, so a new failure has to be reported
+// with the closest existing code.
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum LotteryError {
+    AlreadyInitialized = 1,
+    NotInitialized = 2,
+    TokenNotConfigured = 3,
+    RoundNotFound = 4,
+    // 5 was ActiveRoundExists, returned by builds that ran one round at a
+    // time. It is left unused so no client reads an old code as a new error.
+    RoundInactive = 6,
+    RoundEnded = 7,
+    DuplicateTicket = 8,
+    WrongAmount = 9,
+    RevealNotStarted = 10,
+    RevealWindowClosed = 11,
+    NoCommit = 12,
+    CommitMismatch = 13,
+    AlreadyFinalized = 14,
+    NoParticipants = 15,
+    RevealDeadlineNotPassed = 16,
+    NotEnoughParticipants = 17,
+    RoundNotCancelled = 18,
+    NothingToRefund = 19,
+    RefundAlreadyClaimed = 20,
+    RoundNotFinalized = 21,
+    NotWinner = 22,
+    PrizeAlreadyClaimed = 23,
+    ClaimExpired = 24,
+    ClaimWindowOpen = 25,
+    InvalidClaimWindow = 26,
+    InvalidFee = 27,
+    TreasuryNotConfigured = 28,
+    InvalidPrizeTiers = 29,
+    InvalidTicketCount = 30,
+    CommitCountMismatch = 31,
+    NotTicketOwner = 32,
+    InvalidRevealPolicy = 33,
+    AlreadyRevealed = 34,
+    NotEnoughReveals = 35,
+    InvalidDuration = 36,
+    InvalidDurationBounds = 37,
+    TooManyActiveRounds = 38,
+    NotAuthorized = 39,
+    NoPendingAdmin = 40,
+    InvalidKeeperReward = 41,
+    ContractPaused = 42,
+    NotPaused = 43,
+    RevealNotUsed = 44,
+    DrawNotRecorded = 45,
+    ProviderNotConfigured = 46,
+    RandomnessPending = 47,
+    NoRandomnessRequest = 48,
+    RoundNotCancellable = 49,
+    AlreadyRolledOver = 50,
+    TicketsNotIndexed = 51,
 }
 
 // Admin address
 const ADMIN: Symbol = symbol_short!("ADMIN");
 
+// Address proposed as the next admin, until it accepts
+const PENDING_ADMIN: Symbol = symbol_short!("PEND_ADM");
+
+// Pause state, present only while ticket sales and new rounds are halted
+const PAUSED: Symbol = symbol_short!("PAUSED");
+
 // Counter for lottery rounds
 const ROUND_COUNT: Symbol = symbol_short!("R_COUNT");
 
-// Current active round
-const ACTIVE_ROUND: Symbol = symbol_short!("ACT_RND");
+// IDs of rounds that are neither finalized nor cancelled, oldest first
+const ACTIVE_ROUNDS: Symbol = symbol_short!("ACT_RNDS");
+
+// Upper bound on concurrent rounds, which bounds the size of ACTIVE_ROUNDS
+const MAX_ACTIVE_ROUNDS: u32 = 20;
+
+// Payment tokens rounds can use, mapped to whether new rounds may still use them.
+// Removed tokens stay in the map so their balances remain visible.
+const TOKENS: Symbol = symbol_short!("TOKENS");
+
+// Seconds a winner has to claim their prize after finalization. The cap keeps
+// every claim deadline far from overflowing the ledger clock.
+const CLAIM_WINDOW: Symbol = symbol_short!("CLM_WIN");
+const DEFAULT_CLAIM_WINDOW: u64 = 30 * 24 * 3600;
+const MAX_CLAIM_WINDOW: u64 = 365 * 24 * 3600;
+
+// Allowed sale and reveal durations for new rounds. The longest sale plus
+// the longest reveal window must stay under MAX_ROUND_DURATION, so tickets
+// written on a round's first day are still live when it is drawn, even at
+// the end of its FINALIZE_GRACE.
+const DURATION_BOUNDS: Symbol = symbol_short!("DUR_BND");
+const DEFAULT_MIN_SALE_DURATION: u64 = 3600;
+const DEFAULT_MAX_SALE_DURATION: u64 = 21 * 24 * 3600;
+const DEFAULT_MIN_REVEAL_DURATION: u64 = 3600;
+const DEFAULT_MAX_REVEAL_DURATION: u64 = 7 * 24 * 3600;
+const MAX_ROUND_DURATION: u64 = 28 * 24 * 3600;
+
+// Expired prizes, per token, waiting to seed the next round's pool in that token
+const ROLLOVER: Symbol = symbol_short!("ROLLOVER");
+
+// Protocol fee in basis points, the address that claims it, and the fees
+// taken per token that the treasury has not claimed yet
+const FEE_BPS: Symbol = symbol_short!("FEE_BPS");
+const TREASURY: Symbol = symbol_short!("TREASURY");
+const FEES: Symbol = symbol_short!("FEES");
+const MAX_BPS: u32 = 10_000;
+const MAX_FEE_BPS: u32 = 1_000;
+
+// Share of the pool paid to whoever finalizes a round, in basis points
+const KEEPER_BPS: Symbol = symbol_short!("KEEP_BPS");
+const MAX_KEEPER_BPS: u32 = 500;
+
+// Seconds a round waits for a Finalizer, or a Provider round for its
+// provider, after the reveal deadline before anyone may cancel it
+const FINALIZE_GRACE: u64 = 24 * 3600;
+
+// Randomness provider used by Provider rounds, and how many ledgers (about
+// an hour) a request may go unanswered before finalize_round can repeat it
+const PROVIDER: Symbol = symbol_short!("PROVIDER");
+const RANDOMNESS_TIMEOUT_LEDGERS: u32 = 720;
+
+// Domain separator for randomness request IDs
+const REQUEST_DOMAIN: &[u8] = b"lottery-randomness-v1";
+
+// Upper bound on prize tiers per round, which bounds the draw loop
+const MAX_PRIZE_TIERS: u32 = 10;
+
+// Upper bound on tickets bought in a single buy_tickets call. Each ticket
+// writes three ledger entries on top of about eight per call, so ten keep a
+// batch well inside the network's 50 write entries per transaction.
+const MAX_TICKETS_PER_CALL: u32 = 10;
+
+// Upper bound on entries returned by one call to a paginated view, and a
+// lower one for token_balances, which calls each token contract
+const MAX_PAGE_SIZE: u32 = 100;
+const MAX_BALANCE_PAGE_SIZE: u32 = 10;
+
+// Layout version of stored records. Bump it whenever LotteryRound or another
+// stored type changes shape, and teach migrate_round to convert the old layout.
+// Version 1 is the first upgradable build, whose rounds had no entropy mode,
+// commit scheme or keeper reward, and which kept no seed chain.
+const SCHEMA_VERSION: u32 = 2;
+const SCHEMA: Symbol = symbol_short!("SCHEMA");
+
+// Domain separator for CommitScheme::V2, so a commit can never be replayed
+// as any other hash the contract or its clients compute
+const COMMIT_DOMAIN: &[u8] = b"lottery-commit-v2";
 
-// Token address for payments
-const TOKEN: Symbol = symbol_short!("TOKEN");
+// Longest seed reveal_seed accepts. 32 bytes carry all the entropy a sha256
+// commit can bind, and the cap keeps every ticket's reveal entry small.
+const MAX_SEED_LEN: u32 = 32;
+
+// Next round migrate will rewrite, while a migration is under way
+const MIGRATE_CURSOR: Symbol = symbol_short!("MIG_NEXT");
+
+// TTL policy for per-round entries in persistent storage. Every write, and
+// every read the round still depends on, bumps the entry to ~30 days; it is
+// only bumped again once it drops below ~29 days.
+const DAY_IN_LEDGERS: u32 = 17280;
+const ROUND_TTL_EXTEND: u32 = 30 * DAY_IN_LEDGERS;
+const ROUND_TTL_THRESHOLD: u32 = ROUND_TTL_EXTEND - DAY_IN_LEDGERS;
 
 // Mapping round ID to LotteryRound struct
 #[contracttype]
@@ -42,22 +373,56 @@ pub enum ParticipantBook {
     Participant(u64, u64) // (round_id, participant_index)
 }
 
-// Mapping (round_id, participant_address) to commit hash
+// Mapping (round_id, ticket_index) to commit hash
 #[contracttype]
 pub enum CommitBook {
-    Commit(u64, Address)
+    Commit(u64, u64)
 }
 
-// Mapping (round_id, participant_address) to revealed seed
+// Mapping (round_id, ticket_index) to revealed seed, and (round_id, n) to
+// the RevealStep of the round's n-th reveal, from 1
 #[contracttype]
 pub enum RevealBook {
-    Reveal(u64, Address)
+    Reveal(u64, u64),
+    Step(u64, u64)
 }
 
-// Mapping (round_id, participant_address) to bool for duplicate checking
+// Mapping (round_id, participant_address) to ticket ownership
 #[contracttype]
 pub enum ParticipantMap {
-    HasTicket(u64, Address)
+    HasTicket(u64, Address), // bool, for duplicate checking
+    TicketCount(u64, Address), // u64, number of tickets held
+    RevealCount(u64, Address), // u64, number of tickets revealed
+    Ticket(u64, Address, u64) // u64, ticket index of the participant's n-th ticket, from 1
+}
+
+// Per-address history, so players never need to scan every round
+#[contracttype]
+pub enum PlayerBook {
+    Stats(Address), // PlayerStats
+    Round(Address, u64) // u64, round ID of the address's n-th round, from 1
+}
+
+// Mapping round ID to the DrawRecord written by finalize_round, and to a
+// Provider round's outstanding RandomnessRequest and the RandomnessProvider
+// it was created with
+#[contracttype]
+pub enum DrawBook {
+    Draw(u64),
+    Request(u64),
+    Provider(u64)
+}
+
+// Mapping (role, address) to bool for delegated roles, kept in instance storage
+#[contracttype]
+pub enum RoleBook {
+    Grant(Role, Address)
+}
+
+// Mapping (round_id, participant_address) to bool once a refund is paid out
+#[contracttype]
+pub enum RefundBook {
+    Refunded(u64, Address)
 }
 
 #[contract]
@@ -66,314 +431,1825 @@ pub struct LotteryContract;
 #[contractimpl]
 impl LotteryContract {
     
-    // Initialize the contract with an admin
-    pub fn init_admin(env: Env, admin: Address, token: Address) {
+    // Initialize the contract with an admin and the first allowed payment token
+    pub fn init_admin(env: Env, admin: Address, token: Address) -> Result<(), LotteryError> {
         // Check if already initialized
         if env.storage().instance().has(&ADMIN) {
             log!(&env, "Contract already initialized!");
-            panic!("Contract already initialized!");
+            return Err(LotteryError::AlreadyInitialized);
         }
         
         admin.require_auth();
         
         env.storage().instance().set(&ADMIN, &admin);
-        env.storage().instance().set(&TOKEN, &token);
+        env.storage().instance().set(&SCHEMA, &SCHEMA_VERSION);
+        env.storage().instance().set(&TOKENS, &Map::from_array(&env, [(token, true)]));
         env.storage().instance().extend_ttl(100000, 100000);
         
         log!(&env, "Contract initialized with admin");
+        Ok(())
     }
     
     // Helper function to require admin authorization
-    fn require_admin(env: &Env) {
+    fn require_admin(env: &Env) -> Result<(), LotteryError> {
         let admin: Address = env.storage().instance()
             .get(&ADMIN)
-            .expect("Contract not initialized");
+            .ok_or(LotteryError::NotInitialized)?;
         admin.require_auth();
+        Ok(())
     }
     
-    // Create a new lottery round
-    pub fn create_round(env: Env, ticket_price: i128, duration_hours: u64, allow_multiple: bool) -> u64 {
-        Self::require_admin(&env);
+    // Helper function to require that caller signed and is the admin or holds the role
+    fn require_role(env: &Env, caller: &Address, role: Role) -> Result<(), LotteryError> {
+        let admin: Address = env.storage().instance()
+            .get(&ADMIN)
+            .ok_or(LotteryError::NotInitialized)?;
+        caller.require_auth();
         
-        // Check if there's an active round
-        let active_round_id: u64 = env.storage().instance().get(&ACTIVE_ROUND).unwrap_or(0);
-        if active_round_id != 0 {
-            let active_round = Self::view_round(env.clone(), active_round_id);
-            if active_round.is_active {
-                log!(&env, "Cannot create new round. Active round exists!");
-                panic!("Cannot create new round. Active round exists!");
-            }
+        if *caller != admin && !Self::has_role(env.clone(), caller.clone(), role) {
+            log!(env, "Caller does not hold the required role!");
+            return Err(LotteryError::NotAuthorized);
         }
+        Ok(())
+    }
+    
+    // Propose a new admin (admin only). Nothing changes until they accept.
+    pub fn propose_admin(env: Env, new_admin: Address) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        let mut round_count: u64 = env.storage().instance().get(&ROUND_COUNT).unwrap_or(0);
-        round_count += 1;
+        env.storage().instance().set(&PENDING_ADMIN, &new_admin);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        let current_time = env.ledger().timestamp();
-        let end_time = current_time + (duration_hours * 3600); // Convert hours to seconds
-        let reveal_deadline = end_time + (24 * 3600); // 24 hours after end_time for reveals
+        AdminProposed { new_admin }.publish(&env);
         
-        let new_round = LotteryRound {
-            round_id: round_count,
-            ticket_price,
-            total_pool: 0,
-            participants_count: 0,
-            is_active: true,
-            winner: None,
-            end_time,
-            reveal_deadline,
-            finalized: false,
-            allow_multiple,
-        };
+        log!(&env, "New admin proposed");
+        Ok(())
+    }
+    
+    // Accept a pending admin proposal, signed by the proposed address
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), LotteryError> {
+        new_admin.require_auth();
         
-        env.storage().instance().set(&RoundBook::Round(round_count), &new_round);
-        env.storage().instance().set(&ROUND_COUNT, &round_count);
-        env.storage().instance().set(&ACTIVE_ROUND, &round_count);
+        let pending: Option<Address> = env.storage().instance().get(&PENDING_ADMIN);
+        if pending != Some(new_admin.clone()) {
+            log!(&env, "Address has not been proposed as admin!");
+            return Err(LotteryError::NoPendingAdmin);
+        }
+        
+        let previous_admin: Address = env.storage().instance()
+            .get(&ADMIN)
+            .ok_or(LotteryError::NotInitialized)?;
+        env.storage().instance().set(&ADMIN, &new_admin);
+        env.storage().instance().remove(&PENDING_ADMIN);
         env.storage().instance().extend_ttl(10000, 10000);
         
-        log!(&env, "Lottery Round {} Created with ticket price: {}", round_count, ticket_price);
-        round_count
+        AdminChanged { previous_admin, new_admin }.publish(&env);
+        
+        log!(&env, "Admin transferred");
+        Ok(())
     }
     
-    // Buy a lottery ticket with payment and commit hash
-    pub fn buy_ticket(env: Env, round_id: u64, participant: Address, amount: i128, commit_hash: BytesN<32>) {
-        participant.require_auth();
+    // View the current admin
+    pub fn admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&ADMIN)
+    }
+    
+    // View the address proposed as the next admin, if any
+    pub fn pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&PENDING_ADMIN)
+    }
+    
+    // Grant a role to an address (admin only)
+    pub fn grant_role(env: Env, account: Address, role: Role) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        let mut round = Self::view_round(env.clone(), round_id);
+        env.storage().instance().set(&RoleBook::Grant(role, account.clone()), &true);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        if round.round_id == 0 {
-            log!(&env, "Lottery round not found!");
-            panic!("Lottery round not found!");
-        }
+        RoleGranted { role, account }.publish(&env);
+        Ok(())
+    }
+    
+    // Revoke a role from an address (admin only)
+    pub fn revoke_role(env: Env, account: Address, role: Role) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        if !round.is_active {
-            log!(&env, "Lottery round is not active!");
-            panic!("Lottery round is not active!");
-        }
+        env.storage().instance().remove(&RoleBook::Grant(role, account.clone()));
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        let current_time = env.ledger().timestamp();
-        if current_time >= round.end_time {
-            log!(&env, "Lottery round has ended!");
-            panic!("Lottery round has ended!");
-        }
+        RoleRevoked { role, account }.publish(&env);
+        Ok(())
+    }
+    
+    // View whether an address has been granted a role. The admin holds every
+    // role implicitly, so this only reports explicit grants.
+    pub fn has_role(env: Env, account: Address, role: Role) -> bool {
+        env.storage().instance().has(&RoleBook::Grant(role, account))
+    }
+    
+    // Halt ticket sales and new rounds (admin or pauser). Reveals, finalization,
+    // claims and refunds keep working so nobody's funds are trapped.
+    pub fn pause(env: Env, caller: Address, reason: String) -> Result<(), LotteryError> {
+        Self::require_role(&env, &caller, Role::Pauser)?;
         
-        // Check for duplicate tickets if multiple tickets not allowed
-        if !round.allow_multiple {
-            let participant_map_key = ParticipantMap::HasTicket(round_id, participant.clone());
-            if env.storage().instance().has(&participant_map_key) {
-                log!(&env, "Participant already bought a ticket for this round!");
-                panic!("Participant already bought a ticket for this round!");
-            }
-            // Mark participant as having a ticket
-            env.storage().instance().set(&participant_map_key, &true);
-        }
+        let state = PauseState {
+            reason: reason.clone(),
+            paused_by: caller.clone(),
+            paused_at: env.ledger().timestamp(),
+        };
+        env.storage().instance().set(&PAUSED, &state);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        // Validate payment amount
-        if amount != round.ticket_price {
-            log!(&env, "Incorrect payment amount! Expected: {}, Got: {}", round.ticket_price, amount);
-            panic!("Incorrect payment amount!");
+        Paused { reason, by: caller }.publish(&env);
+        
+        log!(&env, "Contract paused");
+        Ok(())
+    }
+    
+    // Resume ticket sales and new rounds (admin or pauser)
+    pub fn unpause(env: Env, caller: Address) -> Result<(), LotteryError> {
+        Self::require_role(&env, &caller, Role::Pauser)?;
+        
+        if !env.storage().instance().has(&PAUSED) {
+            log!(&env, "Contract is not paused!");
+            return Err(LotteryError::NotPaused);
         }
         
-        // Transfer tokens from participant to contract
-        let token_address: Address = env.storage().instance()
-            .get(&TOKEN)
-            .expect("Token not configured");
-        let token_client = token::Client::new(&env, &token_address);
-        let contract_address = env.current_contract_address();
+        env.storage().instance().remove(&PAUSED);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        // Transfer payment from participant to contract
-        token_client.transfer(&participant, &contract_address, &amount);
+        Unpaused { by: caller }.publish(&env);
         
-        // Update round data
-        round.total_pool += amount;
-        round.participants_count += 1;
+        log!(&env, "Contract unpaused");
+        Ok(())
+    }
+    
+    // View the pause state, or None while the contract is running
+    pub fn pause_state(env: Env) -> Option<PauseState> {
+        env.storage().instance().get(&PAUSED)
+    }
+    
+    // Helper function to reject calls that are halted while paused
+    fn require_not_paused(env: &Env) -> Result<(), LotteryError> {
+        if env.storage().instance().has(&PAUSED) {
+            log!(env, "Contract is paused!");
+            return Err(LotteryError::ContractPaused);
+        }
+        Ok(())
+    }
+    
+    // Helper function to write a per-round entry to persistent storage and bump its TTL
+    fn save_entry<K, V>(env: &Env, key: &K, value: &V)
+    where
+        K: IntoVal<Env, Val>,
+        V: IntoVal<Env, Val>,
+    {
+        env.storage().persistent().set(key, value);
+        env.storage().persistent().extend_ttl(key, ROUND_TTL_THRESHOLD, ROUND_TTL_EXTEND);
+    }
+    
+    // Helper function to read a per-round entry from persistent storage
+    fn load_entry<K, V>(env: &Env, key: &K) -> Option<V>
+    where
+        K: IntoVal<Env, Val>,
+        V: TryFromVal<Env, Val>,
+    {
+        env.storage().persistent().get(key)
+    }
+    
+    // Helper function to read a per-round entry the round will need again,
+    // bumping its TTL as save_entry does so it outlives the round's deadlines
+    fn load_and_extend<K, V>(env: &Env, key: &K) -> Option<V>
+    where
+        K: IntoVal<Env, Val>,
+        V: TryFromVal<Env, Val>,
+    {
+        let value = Self::load_entry(env, key)?;
+        env.storage().persistent().extend_ttl(key, ROUND_TTL_THRESHOLD, ROUND_TTL_EXTEND);
+        Some(value)
+    }
+    
+    // Helper function to load the payment token a round was created with
+    fn token_client<'a>(env: &'a Env, round: &LotteryRound) -> token::Client<'a> {
+        token::Client::new(env, &round.token)
+    }
+    
+    // Helper function to load every token ever allowed, mapped to whether it still is
+    fn tokens(env: &Env) -> Map<Address, bool> {
+        env.storage().instance().get(&TOKENS).unwrap_or(Map::new(env))
+    }
+    
+    // Allow new rounds to take payment in a token (admin only)
+    pub fn allow_token(env: Env, token: Address) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        // Store participant
-        let participant_key = ParticipantBook::Participant(round_id, round.participants_count);
-        env.storage().instance().set(&participant_key, &participant);
+        let mut tokens = Self::tokens(&env);
+        tokens.set(token, true);
+        env.storage().instance().set(&TOKENS, &tokens);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        // Store commit hash for commit-reveal scheme
-        let commit_key = CommitBook::Commit(round_id, participant.clone());
-        env.storage().instance().set(&commit_key, &commit_hash);
+        log!(&env, "Token added to allowlist");
+        Ok(())
+    }
+    
+    // Stop new rounds from using a token (admin only). Rounds already
+    // created with it keep working until they are closed.
+    pub fn disallow_token(env: Env, token: Address) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        // Update round
-        env.storage().instance().set(&RoundBook::Round(round_id), &round);
+        let mut tokens = Self::tokens(&env);
+        if !tokens.contains_key(token.clone()) {
+            log!(&env, "Token is not on the allowlist!");
+            return Err(LotteryError::TokenNotConfigured);
+        }
+        tokens.set(token, false);
+        env.storage().instance().set(&TOKENS, &tokens);
         env.storage().instance().extend_ttl(10000, 10000);
         
-        log!(&env, "Ticket purchased for Round {} by participant #{}", round_id, round.participants_count);
+        log!(&env, "Token removed from allowlist");
+        Ok(())
     }
     
-    // Reveal the seed for commit-reveal scheme
-    pub fn reveal_seed(env: Env, round_id: u64, participant: Address, seed: Bytes) {
-        participant.require_auth();
+    // View the tokens new rounds may use
+    pub fn allowed_tokens(env: Env) -> Vec<Address> {
+        let mut allowed = Vec::new(&env);
+        for (token, is_allowed) in Self::tokens(&env).iter() {
+            if is_allowed {
+                allowed.push_back(token);
+            }
+        }
+        allowed
+    }
+    
+    // View the contract's balance of up to `limit` of the tokens ever
+    // allowed, skipping the first `start`, in the order the allowlist keeps them
+    pub fn token_balances(env: Env, start: u64, limit: u32) -> Map<Address, i128> {
+        let contract_address = env.current_contract_address();
+        let tokens = Self::tokens(&env).keys();
+        let mut balances = Map::new(&env);
+        for n in Self::page_range(start, limit.min(MAX_BALANCE_PAGE_SIZE), tokens.len() as u64) {
+            let token = tokens.get(n as u32 - 1).unwrap();
+            let balance = token::Client::new(&env, &token).balance(&contract_address);
+            balances.set(token, balance);
+        }
+        balances
+    }
+    
+    // Helper function to read the expired prizes waiting to seed a round in a token
+    fn rollover(env: &Env, token: &Address) -> i128 {
+        let rollover: Map<Address, i128> = env.storage().instance().get(&ROLLOVER).unwrap_or(Map::new(env));
+        rollover.get(token.clone()).unwrap_or(0)
+    }
+    
+    // Helper function to set the expired prizes waiting to seed a round in a token
+    fn set_rollover(env: &Env, token: &Address, amount: i128) {
+        let mut rollover: Map<Address, i128> = env.storage().instance().get(&ROLLOVER).unwrap_or(Map::new(env));
+        rollover.set(token.clone(), amount);
+        env.storage().instance().set(&ROLLOVER, &rollover);
+    }
+    
+    // Replace the contract's code with an already uploaded WASM build (admin only).
+    // Storage is kept; call migrate afterwards if the new build bumps the schema.
+    pub fn upgrade(env: Env, new_wasm_hash: BytesN<32>) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
+        
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
         
-        let round = Self::view_round(env.clone(), round_id);
+        log!(&env, "Contract code upgraded");
+        Ok(())
+    }
+    
+    // View the layout version of the records in storage
+    pub fn schema_version(env: Env) -> u32 {
+        env.storage().instance().get(&SCHEMA).unwrap_or(SCHEMA_VERSION)
+    }
+    
+    // Rewrite up to `limit` round records left by an older build into the
+    // current layout (admin only). Returns how many rounds were rewritten;
+    // call it until it returns 0. The schema version is bumped once every
+    // round has been rewritten.
+    pub fn migrate(env: Env, limit: u32) -> Result<u64, LotteryError> {
+        Self::require_admin(&env)?;
         
-        if round.round_id == 0 {
-            log!(&env, "Lottery round not found!");
-            panic!("Lottery round not found!");
+        let from_version = Self::schema_version(env.clone());
+        if from_version == SCHEMA_VERSION || limit == 0 {
+            log!(&env, "Storage is already at schema version {}", from_version);
+            return Ok(0);
         }
         
-        let current_time = env.ledger().timestamp();
+        let round_count = Self::round_count(env.clone());
+        let next: u64 = env.storage().instance().get(&MIGRATE_CURSOR).unwrap_or(1);
+        let last = round_count.min(next + limit as u64 - 1);
         
-        // Must be after end_time but before reveal_deadline
-        if current_time < round.end_time {
-            log!(&env, "Reveal phase has not started yet!");
-            panic!("Reveal phase has not started yet!");
+        for round_id in next..=last {
+            Self::migrate_round(&env, round_id);
         }
         
-        if current_time >= round.reveal_deadline {
-            log!(&env, "Reveal deadline has passed!");
-            panic!("Reveal deadline has passed!");
+        if last >= round_count {
+            env.storage().instance().set(&SCHEMA, &SCHEMA_VERSION);
+            env.storage().instance().remove(&MIGRATE_CURSOR);
+            log!(&env, "Storage migrated from schema version {} to {}", from_version, SCHEMA_VERSION);
+        } else {
+            env.storage().instance().set(&MIGRATE_CURSOR, &(last + 1));
         }
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        // Get stored commit hash
-        let commit_key = CommitBook::Commit(round_id, participant.clone());
-        let stored_commit: BytesN<32> = env.storage().instance()
-            .get(&commit_key)
-            .expect("No commit found for this participant");
+        Ok(last + 1 - next)
+    }
+    
+    // Helper function to rewrite one stored round in the current layout.
+    // get_round already reads the older layout, so saving it back is enough.
+    fn migrate_round(env: &Env, round_id: u64) {
+        if let Some(round) = Self::get_round(env.clone(), round_id) {
+            Self::save_entry(env, &RoundBook::Round(round_id), &round);
+        }
+    }
+    
+    // Set how long winners have to claim their prize, at most a year (admin only)
+    pub fn set_claim_window(env: Env, seconds: u64) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        // Compute hash of seed || participant || round_id
-        let mut hash_input = Bytes::new(&env);
-        hash_input.append(&seed);
-        let addr_bytes: Bytes = participant.to_string().to_bytes();
-        hash_input.append(&addr_bytes);
-        hash_input.append(&Bytes::from_slice(&env, &round_id.to_be_bytes()));
+        if seconds == 0 || seconds > MAX_CLAIM_WINDOW {
+            log!(&env, "Claim window must be positive and at most {} seconds!", MAX_CLAIM_WINDOW);
+            return Err(LotteryError::InvalidClaimWindow);
+        }
         
-        let computed_hash: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        env.storage().instance().set(&CLAIM_WINDOW, &seconds);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        // Verify commit
-        if computed_hash != stored_commit {
-            log!(&env, "Seed does not match commit!");
-            panic!("Seed does not match commit!");
+        log!(&env, "Claim window set to {} seconds", seconds);
+        Ok(())
+    }
+    
+    // View the current claim window in seconds
+    pub fn claim_window(env: Env) -> u64 {
+        env.storage().instance().get(&CLAIM_WINDOW).unwrap_or(DEFAULT_CLAIM_WINDOW)
+    }
+    
+    // Set the bounds new rounds' sale and reveal durations must fall within (admin only)
+    pub fn set_duration_bounds(env: Env, bounds: DurationBounds) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
+        
+        let valid = bounds.min_sale_duration > 0
+            && bounds.min_sale_duration <= bounds.max_sale_duration
+            && bounds.min_reveal_duration > 0
+            && bounds.min_reveal_duration <= bounds.max_reveal_duration
+            && bounds.max_sale_duration.checked_add(bounds.max_reveal_duration).is_some_and(|longest| longest <= MAX_ROUND_DURATION);
+        if !valid {
+            log!(&env, "Duration bounds must be positive with min <= max, and a round can last at most {} seconds!", MAX_ROUND_DURATION);
+            return Err(LotteryError::InvalidDurationBounds);
         }
         
-        // Store revealed seed
-        let reveal_key = RevealBook::Reveal(round_id, participant.clone());
-        env.storage().instance().set(&reveal_key, &seed);
+        env.storage().instance().set(&DURATION_BOUNDS, &bounds);
         env.storage().instance().extend_ttl(10000, 10000);
         
-        log!(&env, "Seed revealed for Round {} by participant", round_id);
+        log!(&env, "Duration bounds updated");
+        Ok(())
+    }
+    
+    // View the bounds for round sale and reveal durations
+    pub fn duration_bounds(env: Env) -> DurationBounds {
+        env.storage().instance().get(&DURATION_BOUNDS).unwrap_or(DurationBounds {
+            min_sale_duration: DEFAULT_MIN_SALE_DURATION,
+            max_sale_duration: DEFAULT_MAX_SALE_DURATION,
+            min_reveal_duration: DEFAULT_MIN_REVEAL_DURATION,
+            max_reveal_duration: DEFAULT_MAX_REVEAL_DURATION,
+        })
     }
     
-    // Finalize round and draw winner using commit-reveal randomness
-    pub fn finalize_round(env: Env, round_id: u64) -> Address {
-        Self::require_admin(&env);
+    // Set the address that claims protocol fees (admin or fee manager)
+    pub fn set_treasury(env: Env, caller: Address, treasury: Address) -> Result<(), LotteryError> {
+        Self::require_role(&env, &caller, Role::FeeManager)?;
         
-        let mut round = Self::view_round(env.clone(), round_id);
+        env.storage().instance().set(&TREASURY, &treasury);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        if round.round_id == 0 {
-            log!(&env, "Lottery round not found!");
-            panic!("Lottery round not found!");
-        }
+        log!(&env, "Treasury updated");
+        Ok(())
+    }
+    
+    // Set the protocol fee taken from each pool, in basis points (admin or
+    // fee manager). Applies to rounds created after the change.
+    pub fn set_fee(env: Env, caller: Address, fee_bps: u32) -> Result<(), LotteryError> {
+        Self::require_role(&env, &caller, Role::FeeManager)?;
         
-        if !round.is_active {
-            log!(&env, "Lottery round is already closed!");
-            panic!("Lottery round is already closed!");
+        if fee_bps > MAX_FEE_BPS {
+            log!(&env, "Fee cannot exceed {} bps!", MAX_FEE_BPS);
+            return Err(LotteryError::InvalidFee);
         }
         
-        if round.finalized {
-            log!(&env, "Round already finalized!");
-            panic!("Round already finalized!");
+        if fee_bps > 0 && !env.storage().instance().has(&TREASURY) {
+            log!(&env, "Set a treasury before charging a fee!");
+            return Err(LotteryError::TreasuryNotConfigured);
         }
         
-        if round.participants_count == 0 {
-            log!(&env, "No participants in this round!");
-            panic!("No participants in this round!");
-        }
+        env.storage().instance().set(&FEE_BPS, &fee_bps);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        let current_time = env.ledger().timestamp();
+        log!(&env, "Protocol fee set to {} bps", fee_bps);
+        Ok(())
+    }
+    
+    // View the protocol fee in basis points
+    pub fn fee_bps(env: Env) -> u32 {
+        env.storage().instance().get(&FEE_BPS).unwrap_or(0)
+    }
+    
+    // Pay the treasury every fee taken in a token so far (treasury only),
+    // returning the amount paid. Fees stay in the contract until claimed, so
+    // finalize_round never depends on the treasury accepting a transfer.
+    pub fn claim_fees(env: Env, token: Address) -> Result<i128, LotteryError> {
+        let treasury = Self::treasury(env.clone()).ok_or(LotteryError::TreasuryNotConfigured)?;
+        treasury.require_auth();
         
-        // Must be after reveal_deadline to ensure all reveals are in
-        if current_time < round.reveal_deadline {
-            log!(&env, "Reveal deadline has not passed yet!");
-            panic!("Reveal deadline has not passed yet!");
+        let amount = Self::fees_accrued(env.clone(), token.clone());
+        if amount == 0 {
+            log!(&env, "No fees to claim");
+            return Ok(0);
         }
         
-        // Combine all revealed seeds using iterative hashing
-        let mut combined_hash = BytesN::<32>::from_array(&env, &[0u8; 32]);
-        let mut reveal_count = 0u64;
+        // Clear the balance before paying out
+        let mut fees: Map<Address, i128> = env.storage().instance().get(&FEES).unwrap_or(Map::new(&env));
+        fees.remove(token.clone());
+        env.storage().instance().set(&FEES, &fees);
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        for i in 1..=round.participants_count {
-            let participant_key = ParticipantBook::Participant(round_id, i);
-            let participant: Address = env.storage().instance().get(&participant_key).unwrap();
-            
-            let reveal_key = RevealBook::Reveal(round_id, participant);
-            if let Some(revealed_seed) = env.storage().instance().get::<_, Bytes>(&reveal_key) {
-                // Combine: h = sha256(h || seed)
-                let mut hash_input = Bytes::new(&env);
-                let combined_bytes: Bytes = Bytes::from_array(&env, &combined_hash.to_array());
-                hash_input.append(&combined_bytes);
-                hash_input.append(&revealed_seed);
-                combined_hash = env.crypto().sha256(&hash_input).into();
-                reveal_count += 1;
-            }
-            // Missing reveals are treated as zero (not included)
-        }
+        token::Client::new(&env, &token).transfer(&env.current_contract_address(), &treasury, &amount);
         
-        log!(&env, "Combined {} revealed seeds out of {} participants", reveal_count, round.participants_count);
+        FeesClaimed {
+            treasury,
+            token,
+            amount,
+        }.publish(&env);
         
-        // Add ledger sequence for additional entropy
-        let sequence = env.ledger().sequence();
-        let mut final_hash_input = Bytes::new(&env);
-        let combined_bytes: Bytes = Bytes::from_array(&env, &combined_hash.to_array());
-        final_hash_input.append(&combined_bytes);
-        final_hash_input.append(&Bytes::from_slice(&env, &sequence.to_be_bytes()));
-        let final_random_hash: BytesN<32> = env.crypto().sha256(&final_hash_input).into();
-        
-        // Convert hash to u64 for selection
-        let mut random_bytes = [0u8; 8];
-        random_bytes.copy_from_slice(&final_random_hash.to_array()[0..8]);
-        let random_number = u64::from_be_bytes(random_bytes);
-        let winner_index = (random_number % round.participants_count) + 1;
-        
-        // Get winner address
-        let winner_key = ParticipantBook::Participant(round_id, winner_index);
-        let winner: Address = env.storage().instance().get(&winner_key).unwrap();
-        
-        log!(&env, "Winner selected: Participant #{} with {} reveals", winner_index, reveal_count);
-        
-        // Transfer prize pool to winner
-        if round.total_pool > 0 {
-            let token_address: Address = env.storage().instance()
-                .get(&TOKEN)
-                .expect("Token not configured");
-            let token_client = token::Client::new(&env, &token_address);
-            let contract_address = env.current_contract_address();
-            
-            // Transfer tokens from contract to winner
-            token_client.transfer(&contract_address, &winner, &round.total_pool);
-            log!(&env, "Prize pool of {} transferred to winner", round.total_pool);
-        }
+        log!(&env, "Protocol fees of {} claimed", amount);
+        Ok(amount)
+    }
+    
+    // View the fees taken in a token that the treasury has not claimed yet
+    pub fn fees_accrued(env: Env, token: Address) -> i128 {
+        let fees: Map<Address, i128> = env.storage().instance().get(&FEES).unwrap_or(Map::new(&env));
+        fees.get(token).unwrap_or(0)
+    }
+    
+    // Set the share of the pool paid to the caller of finalize_round (admin
+    // only). Each round keeps the share in force when it was created.
+    pub fn set_keeper_reward(env: Env, keeper_bps: u32) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
         
-        // Update round ONLY after successful transfer
-        round.is_active = false;
-        round.winner = Some(winner.clone());
-        round.finalized = true;
+        if keeper_bps > MAX_KEEPER_BPS {
+            log!(&env, "Keeper reward cannot exceed {} bps!", MAX_KEEPER_BPS);
+            return Err(LotteryError::InvalidKeeperReward);
+        }
         
-        env.storage().instance().set(&RoundBook::Round(round_id), &round);
-        env.storage().instance().set(&ACTIVE_ROUND, &0u64); // Clear active round
+        env.storage().instance().set(&KEEPER_BPS, &keeper_bps);
         env.storage().instance().extend_ttl(10000, 10000);
         
-        log!(&env, "Round {} finalized. Winner: participant #{}", round_id, winner_index);
-        
-        winner
+        log!(&env, "Keeper reward set to {} bps", keeper_bps);
+        Ok(())
     }
     
-    // View lottery round details
-    pub fn view_round(env: Env, round_id: u64) -> LotteryRound {
-        let key = RoundBook::Round(round_id);
+    // View the keeper reward in basis points
+    pub fn keeper_reward(env: Env) -> u32 {
+        env.storage().instance().get(&KEEPER_BPS).unwrap_or(0)
+    }
+    
+    // Set, or with None remove, the randomness provider new Provider rounds
+    // ask for their value (admin only). Rounds already created keep the
+    // provider they were created with.
+    pub fn set_randomness_provider(env: Env, provider: Option<RandomnessProvider>) -> Result<(), LotteryError> {
+        Self::require_admin(&env)?;
+        
+        match provider {
+            Some(provider) => env.storage().instance().set(&PROVIDER, &provider),
+            None => env.storage().instance().remove(&PROVIDER),
+        }
+        env.storage().instance().extend_ttl(10000, 10000);
         
-        env.storage().instance().get(&key).unwrap_or(LotteryRound {
+        log!(&env, "Randomness provider updated");
+        Ok(())
+    }
+    
+    // View the randomness provider, if one has been set
+    pub fn randomness_provider(env: Env) -> Option<RandomnessProvider> {
+        env.storage().instance().get(&PROVIDER)
+    }
+    
+    // View the randomness provider a Provider round asks for its value, or
+    // None for rounds in other modes
+    pub fn round_provider(env: Env, round_id: u64) -> Option<RandomnessProvider> {
+        let round = Self::get_round(env.clone(), round_id)?;
+        Self::provider_of(&env, &round)
+    }
+    
+    // Helper function to find the provider of a Provider round. Rounds
+    // created before each round kept its provider use the one configured now.
+    fn provider_of(env: &Env, round: &LotteryRound) -> Option<RandomnessProvider> {
+        if round.entropy_mode != EntropyMode::Provider {
+            return None;
+        }
+        Self::load_and_extend(env, &DrawBook::Provider(round.round_id))
+            .or_else(|| Self::randomness_provider(env.clone()))
+    }
+    
+    // View a Provider round's request that the provider has not answered yet
+    pub fn randomness_request(env: Env, round_id: u64) -> Option<RandomnessRequest> {
+        Self::load_entry(&env, &DrawBook::Request(round_id))
+    }
+    
+    // View the treasury address, if one has been set
+    pub fn treasury(env: Env) -> Option<Address> {
+        env.storage().instance().get(&TREASURY)
+    }
+    
+    // Split a pool into (winner share, fee). The fee rounds down, so any
+    // remainder from the division stays with the winner.
+    fn split_pool(pool: i128, fee_bps: u32) -> (i128, i128) {
+        let fee = pool * fee_bps as i128 / MAX_BPS as i128;
+        (pool - fee, fee)
+    }
+    
+    // Split a prize between tiers given in basis points. Each tier rounds
+    // down; the remainder goes to the first tier.
+    fn split_prize(env: &Env, prize_amount: i128, prize_tiers: &Vec<u32>) -> Vec<i128> {
+        let mut prizes = Vec::new(env);
+        let mut distributed = 0i128;
+        for bps in prize_tiers.iter() {
+            let prize = prize_amount * bps as i128 / MAX_BPS as i128;
+            prizes.push_back(prize);
+            distributed += prize;
+        }
+        prizes.set(0, prizes.get(0).unwrap() + prize_amount - distributed);
+        prizes
+    }
+    
+    // Participants a round needs before it can be drawn: one per prize tier,
+    // and at least min_participants
+    fn required_participants(round: &LotteryRound) -> u64 {
+        let tiers = round.prize_tiers.len() as u64;
+        if round.min_participants > tiers { round.min_participants } else { tiers }
+    }
+    
+    // Tickets that can win the draw. Under Exclude only revealed tickets count.
+    fn eligible_tickets(round: &LotteryRound) -> u64 {
+        if !Self::uses_reveals(round) {
+            return round.participants_count;
+        }
+        match round.reveal_policy {
+            RevealPolicy::Exclude => round.revealed_count,
+            RevealPolicy::Bond(_) => round.participants_count,
+        }
+    }
+    
+    // Whether a round draws from revealed seeds; PRNG and provider rounds ignore them
+    fn uses_reveals(round: &LotteryRound) -> bool {
+        Self::mode_uses_reveals(round.entropy_mode)
+    }
+    
+    // Helper function to tell whether an entropy mode draws from revealed seeds
+    fn mode_uses_reveals(entropy_mode: EntropyMode) -> bool {
+        matches!(entropy_mode, EntropyMode::CommitReveal | EntropyMode::CommitRevealPrng)
+    }
+    
+    // Bond posted with each ticket, zero when the round excludes non-revealers
+    fn ticket_bond(round: &LotteryRound) -> i128 {
+        match round.reveal_policy {
+            RevealPolicy::Exclude => 0,
+            RevealPolicy::Bond(bond) => bond,
+        }
+    }
+    
+    // Create a new lottery round. prize_tiers gives each winner's share of
+    // the pool in basis points, 1st place first, and must add up to 10000.
+    // reveal_policy decides what happens to tickets that are never revealed,
+    // and entropy_mode where the draw's randomness comes from.
+    // Callable by the admin or a round operator.
+    pub fn create_round(env: Env, operator: Address, config: RoundConfig) -> Result<u64, LotteryError> {
+        Self::require_role(&env, &operator, Role::RoundOperator)?;
+        Self::require_not_paused(&env)?;
+        
+        let RoundConfig {
+            token,
+            ticket_price,
+            sale_duration,
+            reveal_duration,
+            allow_multiple,
+            min_participants,
+            prize_tiers,
+            reveal_policy,
+            entropy_mode,
+        } = config;
+        
+        if !Self::tokens(&env).get(token.clone()).unwrap_or(false) {
+            log!(&env, "Token is not on the allowlist!");
+            return Err(LotteryError::TokenNotConfigured);
+        }
+        
+        let bounds = Self::duration_bounds(env.clone());
+        let valid_durations = (bounds.min_sale_duration..=bounds.max_sale_duration).contains(&sale_duration)
+            && (bounds.min_reveal_duration..=bounds.max_reveal_duration).contains(&reveal_duration);
+        if !valid_durations {
+            log!(&env, "Sale and reveal durations must be within the configured bounds!");
+            return Err(LotteryError::InvalidDuration);
+        }
+        
+        if let RevealPolicy::Bond(bond) = reveal_policy {
+            if bond <= 0 || !Self::mode_uses_reveals(entropy_mode) {
+                log!(&env, "Reveal bond must be positive, and rounds without reveals take none!");
+                return Err(LotteryError::InvalidRevealPolicy);
+            }
+        }
+        
+        // Provider rounds keep the provider configured now, so the admin
+        // cannot move a round to another one once tickets are sold
+        let provider = if entropy_mode == EntropyMode::Provider {
+            let provider = Self::randomness_provider(env.clone()).ok_or_else(|| {
+                log!(&env, "No randomness provider is configured!");
+                LotteryError::ProviderNotConfigured
+            })?;
+            Some(provider)
+        } else {
+            None
+        };
+        
+        let valid_tiers = !prize_tiers.is_empty()
+            && prize_tiers.len() <= MAX_PRIZE_TIERS
+            && prize_tiers.iter().all(|bps| bps > 0 && bps <= MAX_BPS)
+            && prize_tiers.iter().sum::<u32>() == MAX_BPS;
+        if !valid_tiers {
+            log!(&env, "Prize tiers must be 1 to {} non-zero shares adding up to {} bps!", MAX_PRIZE_TIERS, MAX_BPS);
+            return Err(LotteryError::InvalidPrizeTiers);
+        }
+        
+        // Rounds run side by side, up to a fixed number at once
+        let mut active_rounds = Self::active_rounds(env.clone());
+        if active_rounds.len() >= MAX_ACTIVE_ROUNDS {
+            log!(&env, "Cannot create new round. {} rounds are already active!", MAX_ACTIVE_ROUNDS);
+            return Err(LotteryError::TooManyActiveRounds);
+        }
+        
+        let mut round_count: u64 = env.storage().instance().get(&ROUND_COUNT).unwrap_or(0);
+        round_count += 1;
+        
+        let current_time = env.ledger().timestamp();
+        let (end_time, reveal_deadline) = current_time.checked_add(sale_duration)
+            .and_then(|end_time| Some((end_time, end_time.checked_add(reveal_duration)?)))
+            .ok_or_else(|| {
+                log!(&env, "Round deadlines overflow the ledger clock!");
+                LotteryError::InvalidDuration
+            })?;
+        
+        // Seed the pool with any prizes in this token that expired unclaimed
+        let rollover = Self::rollover(&env, &token);
+        
+        let new_round = LotteryRound {
+            round_id: round_count,
+            token: token.clone(),
+            ticket_price,
+            total_pool: rollover,
+            participants_count: 0,
+            is_active: true,
+            winners: Vec::new(&env),
+            winning_tickets: Vec::new(&env),
+            end_time,
+            reveal_deadline,
+            finalized: false,
+            allow_multiple,
+            min_participants,
+            cancelled: false,
+            prize_tiers,
+            prizes: Vec::new(&env),
+            prizes_claimed: Vec::new(&env),
+            prize_amount: 0,
+            claim_deadline: 0,
+            prize_expired: false,
+            fee_bps: Self::fee_bps(env.clone()),
+            fee_amount: 0,
+            keeper_bps: Self::keeper_reward(env.clone()),
+            reveal_policy,
+            revealed_count: 0,
+            forfeited_bonds: 0,
+            entropy_mode,
+            commit_scheme: CommitScheme::V2,
+        };
+        
+        Self::save_entry(&env, &RoundBook::Round(round_count), &new_round);
+        if let Some(provider) = provider {
+            Self::save_entry(&env, &DrawBook::Provider(round_count), &provider);
+        }
+        env.storage().instance().set(&ROUND_COUNT, &round_count);
+        active_rounds.push_back(round_count);
+        env.storage().instance().set(&ACTIVE_ROUNDS, &active_rounds);
+        Self::set_rollover(&env, &token, 0);
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        RoundCreated {
+            round_id: round_count,
+            token,
+            ticket_price,
+            initial_pool: rollover,
+            end_time,
+            reveal_deadline,
+            allow_multiple,
+        }.publish(&env);
+        
+        log!(&env, "Lottery Round {} Created with ticket price: {}", round_count, ticket_price);
+        Ok(round_count)
+    }
+    
+    // Helper function to load a round that is still selling tickets
+    fn open_round(env: &Env, round_id: u64) -> Result<LotteryRound, LotteryError> {
+        Self::require_not_paused(env)?;
+        
+        let round = Self::require_round(env, round_id)?;
+        
+        if !round.is_active {
+            log!(env, "Lottery round is not active!");
+            return Err(LotteryError::RoundInactive);
+        }
+        
+        let current_time = env.ledger().timestamp();
+        if current_time >= round.end_time {
+            log!(env, "Lottery round has ended!");
+            return Err(LotteryError::RoundEnded);
+        }
+        
+        Ok(round)
+    }
+    
+    // Helper function to take payment for, and record, one ticket per commit hash.
+    // Every ticket gets its own slot and commit, so odds follow tickets held.
+    fn issue_tickets(env: &Env, mut round: LotteryRound, participant: &Address, commits: &Vec<BytesN<32>>) -> Result<(), LotteryError> {
+        let round_id = round.round_id;
+        let count = commits.len() as u64;
+        
+        // Check for duplicate tickets if multiple tickets not allowed
+        if !round.allow_multiple {
+            let participant_map_key = ParticipantMap::HasTicket(round_id, participant.clone());
+            if count > 1 || env.storage().persistent().has(&participant_map_key) {
+                log!(env, "Participant already bought a ticket for this round!");
+                return Err(LotteryError::DuplicateTicket);
+            }
+            // Mark participant as having a ticket
+            Self::save_entry(env, &participant_map_key, &true);
+        }
+        
+        // Transfer payment, plus any reveal bond, from participant to contract
+        let amount = round.ticket_price * count as i128;
+        let bond = Self::ticket_bond(&round) * count as i128;
+        let token_client = Self::token_client(env, &round);
+        token_client.transfer(participant, env.current_contract_address(), &(amount + bond));
+        
+        round.total_pool += amount;
+        
+        // Track how many tickets this participant holds for refunds
+        let count_key = ParticipantMap::TicketCount(round_id, participant.clone());
+        let mut ticket_count: u64 = Self::load_entry(env, &count_key).unwrap_or(0);
+        
+        // Record the purchase in the participant's history
+        let mut stats = Self::player_stats(env.clone(), participant.clone());
+        if ticket_count == 0 {
+            stats.rounds_played += 1;
+            Self::save_entry(env, &PlayerBook::Round(participant.clone(), stats.rounds_played), &round_id);
+        }
+        stats.tickets_bought += count;
+        let spent = stats.total_spent.get(round.token.clone()).unwrap_or(0);
+        stats.total_spent.set(round.token.clone(), spent + amount);
+        Self::save_entry(env, &PlayerBook::Stats(participant.clone()), &stats);
+        
+        for commit_hash in commits.iter() {
+            round.participants_count += 1;
+            let ticket_index = round.participants_count;
+            ticket_count += 1;
+            
+            // Store ticket owner and its commit hash for commit-reveal scheme,
+            // and index the ticket under its owner for tickets_of
+            Self::save_entry(env, &ParticipantBook::Participant(round_id, ticket_index), participant);
+            Self::save_entry(env, &CommitBook::Commit(round_id, ticket_index), &commit_hash);
+            Self::save_entry(env, &ParticipantMap::Ticket(round_id, participant.clone(), ticket_count), &ticket_index);
+            
+            TicketPurchased {
+                round_id,
+                buyer: participant.clone(),
+                ticket_index,
+                amount: round.ticket_price,
+            }.publish(env);
+        }
+        
+        Self::save_entry(env, &count_key, &ticket_count);
+        
+        // Update round
+        Self::save_entry(env, &RoundBook::Round(round_id), &round);
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        log!(env, "{} tickets purchased for Round {}, {} sold in total", count, round_id, round.participants_count);
+        Ok(())
+    }
+    
+    // Buy a lottery ticket with payment and commit hash
+    pub fn buy_ticket(env: Env, round_id: u64, participant: Address, amount: i128, commit_hash: BytesN<32>) -> Result<(), LotteryError> {
+        participant.require_auth();
+        
+        let round = Self::open_round(&env, round_id)?;
+        
+        // Validate payment amount
+        if amount != round.ticket_price {
+            log!(&env, "Incorrect payment amount! Expected: {}, Got: {}", round.ticket_price, amount);
+            return Err(LotteryError::WrongAmount);
+        }
+        
+        Self::issue_tickets(&env, round, &participant, &Vec::from_array(&env, [commit_hash]))
+    }
+    
+    // Buy several tickets in one call, paying ticket_price for each.
+    // commits holds one commit hash per ticket, in ticket order.
+    pub fn buy_tickets(env: Env, round_id: u64, participant: Address, count: u32, commits: Vec<BytesN<32>>) -> Result<(), LotteryError> {
+        participant.require_auth();
+        
+        let round = Self::open_round(&env, round_id)?;
+        
+        if count == 0 || count > MAX_TICKETS_PER_CALL {
+            log!(&env, "Ticket count must be between 1 and {}!", MAX_TICKETS_PER_CALL);
+            return Err(LotteryError::InvalidTicketCount);
+        }
+        
+        if commits.len() != count {
+            log!(&env, "Expected {} commit hashes, got {}!", count, commits.len());
+            return Err(LotteryError::CommitCountMismatch);
+        }
+        
+        Self::issue_tickets(&env, round, &participant, &commits)
+    }
+    
+    // Reveal the seed committed for one ticket, returning its bond if the round took one
+    pub fn reveal_seed(env: Env, round_id: u64, participant: Address, ticket_index: u64, seed: Bytes) -> Result<(), LotteryError> {
+        participant.require_auth();
+        
+        let mut round = Self::require_round(&env, round_id)?;
+        
+        let current_time = env.ledger().timestamp();
+        
+        // Must be after end_time but before reveal_deadline
+        if current_time < round.end_time {
+            log!(&env, "Reveal phase has not started yet!");
+            return Err(LotteryError::RevealNotStarted);
+        }
+        
+        if current_time >= round.reveal_deadline {
+            log!(&env, "Reveal deadline has passed!");
+            return Err(LotteryError::RevealWindowClosed);
+        }
+        
+        if !Self::uses_reveals(&round) {
+            log!(&env, "This round does not draw from seeds, they are not revealed!");
+            return Err(LotteryError::RevealNotUsed);
+        }
+        
+        // Only the ticket's owner can reveal for it
+        let owner: Address = Self::load_and_extend(&env, &ParticipantBook::Participant(round_id, ticket_index))
+            .ok_or(LotteryError::NoCommit)?;
+        if owner != participant {
+            log!(&env, "Ticket #{} belongs to another participant!", ticket_index);
+            return Err(LotteryError::NotTicketOwner);
+        }
+        
+        let reveal_key = RevealBook::Reveal(round_id, ticket_index);
+        if env.storage().persistent().has(&reveal_key) {
+            log!(&env, "Ticket #{} is already revealed!", ticket_index);
+            return Err(LotteryError::AlreadyRevealed);
+        }
+        
+        // Get stored commit hash
+        let commit_key = CommitBook::Commit(round_id, ticket_index);
+        let stored_commit: BytesN<32> = Self::load_and_extend(&env, &commit_key)
+            .ok_or(LotteryError::NoCommit)?;
+        
+        // A longer seed is never a valid opening, whatever it hashes to
+        if seed.len() > MAX_SEED_LEN {
+            log!(&env, "Seeds are at most {} bytes!", MAX_SEED_LEN);
+            return Err(LotteryError::CommitMismatch);
+        }
+        
+        // Verify commit, under the scheme the round was created with
+        let computed_hash = Self::commit_hash(&env, round.commit_scheme, round_id, &participant, &seed);
+        if computed_hash != stored_commit {
+            log!(&env, "Seed does not match commit!");
+            return Err(LotteryError::CommitMismatch);
+        }
+        
+        // Store revealed seed, and fold it into the round's seed chain so
+        // finalize_round never has to walk the tickets
+        Self::save_entry(&env, &reveal_key, &seed);
+        
+        let previous_hash = Self::seed_hash(&env, &round);
+        round.revealed_count += 1;
+        if let Some(previous_hash) = previous_hash {
+            let step = RevealStep {
+                ticket_index,
+                seed_hash: randomness::chain(&env, &previous_hash, &seed),
+            };
+            Self::save_entry(&env, &RevealBook::Step(round_id, round.revealed_count), &step);
+        }
+        Self::save_entry(&env, &RoundBook::Round(round_id), &round);
+        
+        let reveal_count_key = ParticipantMap::RevealCount(round_id, participant.clone());
+        let revealed: u64 = Self::load_entry(&env, &reveal_count_key).unwrap_or(0);
+        Self::save_entry(&env, &reveal_count_key, &(revealed + 1));
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        // Hand back the bond now that the ticket has done its part
+        let bond = Self::ticket_bond(&round);
+        if bond > 0 {
+            let token_client = Self::token_client(&env, &round);
+            token_client.transfer(&env.current_contract_address(), &participant, &bond);
+        }
+        
+        SeedRevealed { round_id, participant, ticket_index }.publish(&env);
+        
+        log!(&env, "Seed revealed for Round {} ticket #{}", round_id, ticket_index);
+        Ok(())
+    }
+    
+    // Helper function to read a round's seed chain after its last reveal, or
+    // None if its reveals began under the schema version 1 build, which kept
+    // no chain. Those rounds cannot be drawn and are cancelled instead.
+    fn seed_hash(env: &Env, round: &LotteryRound) -> Option<BytesN<32>> {
+        if round.revealed_count == 0 {
+            return Some(BytesN::from_array(env, &[0u8; 32]));
+        }
+        let step: RevealStep = Self::load_and_extend(env, &RevealBook::Step(round.round_id, round.revealed_count))?;
+        Some(step.seed_hash)
+    }
+    
+    // Helper function to find the ticket behind a round's n-th reveal
+    fn revealed_ticket(env: &Env, round: &LotteryRound, n: u64) -> Option<u64> {
+        let step: RevealStep = Self::load_and_extend(env, &RevealBook::Step(round.round_id, n))?;
+        Some(step.ticket_index)
+    }
+    
+    // Helper function to compute a ticket's commit hash from its seed
+    fn commit_hash(env: &Env, scheme: CommitScheme, round_id: u64, participant: &Address, seed: &Bytes) -> BytesN<32> {
+        let mut hash_input = Bytes::new(env);
+        match scheme {
+            CommitScheme::V1 => {
+                hash_input.append(seed);
+                hash_input.append(&participant.to_string().to_bytes());
+                hash_input.append(&Bytes::from_slice(env, &round_id.to_be_bytes()));
+            }
+            CommitScheme::V2 => {
+                // Every field before the seed has a fixed or self-delimiting
+                // encoding, so no two inputs share a preimage
+                hash_input.append(&Bytes::from_slice(env, COMMIT_DOMAIN));
+                hash_input.append(&env.current_contract_address().to_xdr(env));
+                hash_input.append(&Bytes::from_slice(env, &round_id.to_be_bytes()));
+                hash_input.append(&participant.clone().to_xdr(env));
+                hash_input.append(seed);
+            }
+        }
+        env.crypto().sha256(&hash_input).into()
+    }
+    
+    // Finalize round and draw winner using commit-reveal randomness.
+    // The prize is only recorded here; the winner collects it with claim_prize.
+    // Callable once the reveal deadline has passed: by anyone for Provider
+    // rounds, by the admin or a Finalizer otherwise.
+    pub fn finalize_round(env: Env, caller: Address, round_id: u64) -> Result<Vec<Address>, LotteryError> {
+        let round = Self::require_round(&env, round_id)?;
+        
+        // Every mode but Provider draws inside this call. A CommitReveal draw
+        // adds only the ledger sequence to seeds that are all public by now,
+        // so the caller picks the ledger by choosing when to submit. env.prng()
+        // cannot be predicted, but it is drawn in the caller's transaction: a
+        // contract can call finalize_round, read the winners and revert unless
+        // it won, and simply try again next ledger. Those rounds are left to
+        // trusted finalizers, and cancel_round opens to everyone if none of
+        // them has finalized within FINALIZE_GRACE. A Provider round is drawn
+        // in the provider's own transaction, so here anyone may ask for it.
+        if round.entropy_mode == EntropyMode::Provider {
+            caller.require_auth();
+        } else {
+            Self::require_role(&env, &caller, Role::Finalizer)?;
+        }
+        Self::require_drawable(&env, &round)?;
+        
+        // Provider rounds are drawn when the provider answers through
+        // fulfill_randomness, so this call only asks for the value
+        if round.entropy_mode == EntropyMode::Provider {
+            Self::request_randomness(&env, &round, &caller)?;
+            return Ok(Vec::new(&env));
+        }
+        
+        Self::draw_round(&env, round, &caller, None)
+    }
+    
+    // Callback for the randomness provider of a Provider round: checks the
+    // signature is the provider key's ed25519 signature over the round's
+    // outstanding request ID, then draws the round with sha256(signature) as
+    // its randomness. The keeper reward goes to whoever called
+    // finalize_round. Panics if the signature does not verify.
+    pub fn fulfill_randomness(env: Env, round_id: u64, signature: BytesN<64>) -> Result<Vec<Address>, LotteryError> {
+        let round = Self::require_round(&env, round_id)?;
+        let request_key = DrawBook::Request(round_id);
+        let request: RandomnessRequest = Self::load_entry(&env, &request_key)
+            .ok_or(LotteryError::NoRandomnessRequest)?;
+        request.provider.contract.require_auth();
+        Self::require_drawable(&env, &round)?;
+        
+        // The provider signs only the request ID and the value is derived
+        // from the signature, so the provider cannot pick it. Ed25519 signing
+        // is deterministic, giving one value per request and key. The check
+        // cannot tell a signer who deviates from the standard nonce, so the
+        // provider's key is still trusted to sign honestly, but an honest
+        // provider has no choice to make.
+        let message = Bytes::from_array(&env, &request.request_id.to_array());
+        env.crypto().ed25519_verify(&request.provider.public_key, &message, &signature);
+        let randomness: BytesN<32> = env.crypto().sha256(&Bytes::from_array(&env, &signature.to_array())).into();
+        
+        env.storage().persistent().remove(&request_key);
+        
+        RandomnessFulfilled {
+            round_id,
+            request_id: request.request_id,
+            randomness: randomness.clone(),
+            signature,
+        }.publish(&env);
+        
+        Self::draw_round(&env, round, &request.requested_by, Some(randomness))
+    }
+    
+    // Helper function to ask a Provider round's provider for its randomness.
+    // A request still within its timeout is not repeated; one past it goes
+    // to the same provider again.
+    fn request_randomness(env: &Env, round: &LotteryRound, caller: &Address) -> Result<(), LotteryError> {
+        let round_id = round.round_id;
+        let sequence = env.ledger().sequence();
+        if let Some(pending) = Self::randomness_request(env.clone(), round_id) {
+            if sequence < pending.ledger_sequence.saturating_add(RANDOMNESS_TIMEOUT_LEDGERS) {
+                log!(env, "Randomness for round {} is already requested!", round_id);
+                return Err(LotteryError::RandomnessPending);
+            }
+        }
+        
+        let provider = Self::provider_of(env, round).ok_or_else(|| {
+            log!(env, "Round {} has no randomness provider!", round_id);
+            LotteryError::ProviderNotConfigured
+        })?;
+        
+        // Unique per contract, round and attempt, so an answer can only be
+        // used for the request it was made for
+        let mut hash_input = Bytes::from_slice(env, REQUEST_DOMAIN);
+        hash_input.append(&env.current_contract_address().to_xdr(env));
+        hash_input.append(&Bytes::from_slice(env, &round_id.to_be_bytes()));
+        hash_input.append(&Bytes::from_slice(env, &sequence.to_be_bytes()));
+        let request_id: BytesN<32> = env.crypto().sha256(&hash_input).into();
+        
+        let request = RandomnessRequest {
+            provider: provider.clone(),
+            request_id: request_id.clone(),
+            requested_by: caller.clone(),
+            ledger_sequence: sequence,
+        };
+        Self::save_entry(env, &DrawBook::Request(round_id), &request);
+        
+        RandomnessProviderClient::new(env, &provider.contract).request_randomness(&env.current_contract_address(), &round_id, &request_id);
+        
+        RandomnessRequested {
+            round_id,
+            provider: provider.contract,
+            request_id,
+        }.publish(env);
+        
+        log!(env, "Randomness requested for round {}", round_id);
+        Ok(())
+    }
+    
+    // Helper function to check a round can be drawn now
+    fn require_drawable(env: &Env, round: &LotteryRound) -> Result<(), LotteryError> {
+        if !round.is_active {
+            log!(env, "Lottery round is already closed!");
+            return Err(LotteryError::RoundInactive);
+        }
+        
+        if round.finalized {
+            log!(env, "Round already finalized!");
+            return Err(LotteryError::AlreadyFinalized);
+        }
+        
+        if round.participants_count == 0 {
+            log!(env, "No participants in this round!");
+            return Err(LotteryError::NoParticipants);
+        }
+        
+        let required = Self::required_participants(round);
+        if round.participants_count < required {
+            log!(env, "Round did not reach {} participants, cancel it instead!", required);
+            return Err(LotteryError::NotEnoughParticipants);
+        }
+        
+        let current_time = env.ledger().timestamp();
+        
+        // Must be after reveal_deadline to ensure all reveals are in
+        if current_time < round.reveal_deadline {
+            log!(env, "Reveal deadline has not passed yet!");
+            return Err(LotteryError::RevealDeadlineNotPassed);
+        }
+        
+        let eligible = Self::eligible_tickets(round);
+        if eligible < round.prize_tiers.len() as u64 {
+            log!(env, "Only {} tickets were revealed, cancel the round instead!", eligible);
+            return Err(LotteryError::NotEnoughReveals);
+        }
+        
+        if Self::seed_hash(env, round).is_none() {
+            log!(env, "Reveals began before the upgrade and have no seed chain, cancel the round instead!");
+            return Err(LotteryError::NotEnoughReveals);
+        }
+        
+        Ok(())
+    }
+    
+    // Helper function to draw a round's winners and settle its pool. In
+    // Provider rounds `provider_output` is the provider's verified value.
+    fn draw_round(env: &Env, mut round: LotteryRound, caller: &Address, provider_output: Option<BytesN<32>>) -> Result<Vec<Address>, LotteryError> {
+        let round_id = round.round_id;
+        let current_time = env.ledger().timestamp();
+        let eligible = Self::eligible_tickets(&round);
+        
+        // reveal_seed has already chained the revealed seeds together. Missing
+        // reveals add no entropy and are penalized by the round's reveal policy.
+        let uses_reveals = Self::uses_reveals(&round);
+        let reveal_count = round.revealed_count;
+        let combined_hash = Self::seed_hash(env, &round).ok_or(LotteryError::NotEnoughReveals)?;
+        
+        log!(env, "Combined {} revealed seeds out of {} tickets", reveal_count, round.participants_count);
+        
+        // Mix the ledger sequence and the PRNG or provider output into the
+        // seed chain as the round's mode asks, and publish every input so
+        // anyone can recompute the final hash
+        let sequence = uses_reveals.then(|| env.ledger().sequence());
+        let prng_output = match round.entropy_mode {
+            EntropyMode::CommitReveal => None,
+            EntropyMode::CommitRevealPrng | EntropyMode::Prng => Some(randomness::prng_output(env)),
+            EntropyMode::Provider => provider_output,
+        };
+        let final_random_hash = randomness::final_hash(env, &combined_hash, sequence, prng_output.as_ref());
+        
+        DrawEntropy {
+            round_id,
+            mode: round.entropy_mode,
+            seed_hash: uses_reveals.then(|| combined_hash.clone()),
+            ledger_sequence: sequence,
+            prng_output: prng_output.clone(),
+            final_hash: final_random_hash.clone(),
+        }.publish(env);
+        
+        // Draw one distinct ticket per prize tier. Slots number the eligible
+        // tickets from 1; under Exclude slot k is the k-th revealed ticket,
+        // otherwise (Bond, or a PRNG-only round) it is ticket k.
+        let revealed_only = uses_reveals && round.reveal_policy == RevealPolicy::Exclude;
+        let slots = randomness::draw_slots(env, &final_random_hash, eligible, round.prize_tiers.len());
+        // Every slot is within the eligible tickets, so each maps to a ticket
+        let winning_tickets = Self::slots_to_tickets(env, &round, &slots, revealed_only).unwrap();
+        let mut winners = Vec::new(env);
+        for (draw, winner_index) in winning_tickets.iter().enumerate() {
+            let draw = draw as u32;
+            
+            // Get winner address
+            let winner_key = ParticipantBook::Participant(round_id, winner_index);
+            let winner: Address = Self::load_and_extend(env, &winner_key).unwrap();
+            
+            log!(env, "Tier {} winner selected: Participant #{} with {} reveals", draw, winner_index, reveal_count);
+            
+            WinnerSelected {
+                round_id,
+                tier: draw,
+                winner: winner.clone(),
+                winner_index,
+                reveal_count,
+                entropy_hash: randomness::draw_hash(env, &final_random_hash, draw),
+            }.publish(env);
+            
+            winners.push_back(winner);
+        }
+        
+        // Keep the transcript so verify_draw and off-chain verifiers can
+        // recompute the result
+        let record = DrawRecord {
+            entropy_mode: round.entropy_mode,
+            combined_hash,
+            ledger_sequence: sequence,
+            prng_output,
+            final_hash: final_random_hash,
+            reveal_count,
+            eligible_count: eligible,
+            revealed_only,
+            winning_tickets: winning_tickets.clone(),
+        };
+        Self::save_entry(env, &DrawBook::Draw(round_id), &record);
+        
+        // Bonds of unrevealed tickets join the pool
+        let forfeited_bonds = Self::ticket_bond(&round) * (round.participants_count - reveal_count) as i128;
+        if forfeited_bonds > 0 {
+            log!(env, "{} in reveal bonds forfeited to the pool", forfeited_bonds);
+        }
+        round.total_pool += forfeited_bonds;
+        round.forfeited_bonds = forfeited_bonds;
+        
+        // Pay the keeper, take the protocol fee from what is left, then
+        // record the rest for the winners to claim
+        let token_client = Self::token_client(env, &round);
+        let keeper_amount = round.total_pool * round.keeper_bps as i128 / MAX_BPS as i128;
+        let (prize_amount, fee_amount) = Self::split_pool(round.total_pool - keeper_amount, round.fee_bps);
+        if fee_amount > 0 {
+            let mut fees: Map<Address, i128> = env.storage().instance().get(&FEES).unwrap_or(Map::new(env));
+            let accrued = fees.get(round.token.clone()).unwrap_or(0);
+            fees.set(round.token.clone(), accrued + fee_amount);
+            env.storage().instance().set(&FEES, &fees);
+            log!(env, "Protocol fee of {} set aside for the treasury", fee_amount);
+            
+            FeeCollected {
+                round_id,
+                token: round.token.clone(),
+                amount: fee_amount,
+            }.publish(env);
+        }
+        if keeper_amount > 0 {
+            token_client.transfer(&env.current_contract_address(), caller, &keeper_amount);
+            log!(env, "Keeper reward of {} paid to caller", keeper_amount);
+            
+            KeeperRewarded {
+                round_id,
+                keeper: caller.clone(),
+                amount: keeper_amount,
+            }.publish(env);
+        }
+        
+        let mut prizes_claimed = Vec::new(env);
+        for _ in 0..round.prize_tiers.len() {
+            prizes_claimed.push_back(false);
+        }
+        
+        round.is_active = false;
+        round.winners = winners.clone();
+        round.winning_tickets = winning_tickets;
+        round.finalized = true;
+        round.prizes = Self::split_prize(env, prize_amount, &round.prize_tiers);
+        round.prizes_claimed = prizes_claimed;
+        round.prize_amount = prize_amount;
+        round.fee_amount = fee_amount;
+        round.claim_deadline = current_time + Self::claim_window(env.clone());
+        
+        // Credit each tier's prize to its winner's history
+        for (winner, prize) in round.winners.iter().zip(round.prizes.iter()) {
+            let mut stats = Self::player_stats(env.clone(), winner.clone());
+            stats.wins += 1;
+            let won = stats.total_winnings.get(round.token.clone()).unwrap_or(0);
+            stats.total_winnings.set(round.token.clone(), won + prize);
+            Self::save_entry(env, &PlayerBook::Stats(winner), &stats);
+        }
+        
+        Self::save_entry(env, &RoundBook::Round(round_id), &round);
+        Self::remove_active_round(env, round_id);
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        log!(env, "Round {} finalized with {} winners", round_id, winners.len());
+        
+        Ok(winners)
+    }
+    
+    // Pay out every prize tier an address won in a finalized round, before the claim deadline
+    pub fn claim_prize(env: Env, round_id: u64, winner: Address) -> Result<i128, LotteryError> {
+        winner.require_auth();
+        
+        let mut round = Self::require_round(&env, round_id)?;
+        
+        if !round.finalized {
+            log!(&env, "Round is not finalized yet!");
+            return Err(LotteryError::RoundNotFinalized);
+        }
+        
+        if !round.winners.contains(&winner) {
+            log!(&env, "Address is not a winner of this round!");
+            return Err(LotteryError::NotWinner);
+        }
+        
+        if round.prize_expired || env.ledger().timestamp() >= round.claim_deadline {
+            log!(&env, "Claim window has closed!");
+            return Err(LotteryError::ClaimExpired);
+        }
+        
+        // Mark every tier won by this address as claimed before paying out,
+        // and report the part of the round's fee those tiers paid
+        let tier_fees = Self::split_prize(&env, round.fee_amount, &round.prize_tiers);
+        let mut tiers = Vec::new(&env);
+        let mut amount = 0i128;
+        let mut fee_amount = 0i128;
+        for (tier, tier_winner) in round.winners.iter().enumerate() {
+            let tier = tier as u32;
+            if tier_winner == winner && !round.prizes_claimed.get(tier).unwrap() {
+                round.prizes_claimed.set(tier, true);
+                tiers.push_back(tier);
+                amount += round.prizes.get(tier).unwrap();
+                fee_amount += tier_fees.get(tier).unwrap();
+            }
+        }
+        
+        if tiers.is_empty() {
+            log!(&env, "Prize already claimed!");
+            return Err(LotteryError::PrizeAlreadyClaimed);
+        }
+        
+        Self::save_entry(&env, &RoundBook::Round(round_id), &round);
+        
+        if amount > 0 {
+            let token_client = Self::token_client(&env, &round);
+            token_client.transfer(&env.current_contract_address(), &winner, &amount);
+        }
+        
+        PrizePaid {
+            round_id,
+            winner,
+            tiers,
+            amount,
+            fee_amount,
+        }.publish(&env);
+        
+        log!(&env, "Prize of {} claimed for Round {}", amount, round_id);
+        Ok(amount)
+    }
+    
+    // Roll unclaimed prizes into the next round once the claim window has closed
+    pub fn expire_prize(env: Env, round_id: u64) -> Result<i128, LotteryError> {
+        let mut round = Self::require_round(&env, round_id)?;
+        
+        if !round.finalized {
+            log!(&env, "Round is not finalized yet!");
+            return Err(LotteryError::RoundNotFinalized);
+        }
+        
+        if round.prize_expired {
+            log!(&env, "Prize already rolled over!");
+            return Err(LotteryError::AlreadyRolledOver);
+        }
+        
+        if env.ledger().timestamp() < round.claim_deadline {
+            log!(&env, "Claim window is still open!");
+            return Err(LotteryError::ClaimWindowOpen);
+        }
+        
+        let mut unclaimed = 0i128;
+        let mut any_unclaimed = false;
+        for (tier, claimed) in round.prizes_claimed.iter().enumerate() {
+            if !claimed {
+                unclaimed += round.prizes.get(tier as u32).unwrap();
+                any_unclaimed = true;
+            }
+        }
+        
+        if !any_unclaimed {
+            log!(&env, "All prizes already claimed!");
+            return Err(LotteryError::PrizeAlreadyClaimed);
+        }
+        
+        round.prize_expired = true;
+        Self::save_entry(&env, &RoundBook::Round(round_id), &round);
+        
+        let rollover = Self::rollover(&env, &round.token);
+        Self::set_rollover(&env, &round.token, rollover + unclaimed);
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        PrizeExpired {
+            round_id,
+            amount: unclaimed,
+        }.publish(&env);
+        
+        log!(&env, "Unclaimed prizes of {} from Round {} rolled over", unclaimed, round_id);
+        Ok(unclaimed)
+    }
+    
+    // Cancel a round so every buyer can claim back their tickets.
+    // The admin may cancel a round while its ticket sales are open. After
+    // that a round can only be cancelled once it cannot be drawn: sales closed
+    // without enough participants, the reveal window closed without enough
+    // eligible tickets to fill every prize tier or with reveals begun before
+    // the upgrade from schema version 1, or the round went FINALIZE_GRACE
+    // past its reveal deadline without a finalizer or its provider drawing
+    // it. Anyone may cancel it then.
+    pub fn cancel_round(env: Env, round_id: u64) -> Result<(), LotteryError> {
+        let mut round = Self::require_round(&env, round_id)?;
+        
+        if round.finalized {
+            log!(&env, "Round already finalized!");
+            return Err(LotteryError::AlreadyFinalized);
+        }
+        
+        if !round.is_active {
+            log!(&env, "Lottery round is already closed!");
+            return Err(LotteryError::RoundInactive);
+        }
+        
+        let current_time = env.ledger().timestamp();
+        let threshold_missed = current_time >= round.end_time
+            && round.participants_count < Self::required_participants(&round);
+        let reveals_missed = current_time >= round.reveal_deadline
+            && (Self::eligible_tickets(&round) < round.prize_tiers.len() as u64 || Self::seed_hash(&env, &round).is_none());
+        let finalize_missed = current_time >= round.reveal_deadline.saturating_add(FINALIZE_GRACE);
+        if !threshold_missed && !reveals_missed && !finalize_missed {
+            // Once sales close buyers have committed to the draw, so the
+            // admin can no longer call it off
+            if current_time >= round.end_time {
+                log!(&env, "Round {} can still be drawn, it cannot be cancelled!", round_id);
+                return Err(LotteryError::RoundNotCancellable);
+            }
+            Self::require_admin(&env)?;
+        }
+        
+        round.is_active = false;
+        round.cancelled = true;
+        
+        Self::save_entry(&env, &RoundBook::Round(round_id), &round);
+        env.storage().persistent().remove(&DrawBook::Request(round_id));
+        
+        // Ticket sales are refunded; any pool carried in from expired prizes moves on again
+        let carried_in = round.total_pool - round.ticket_price * round.participants_count as i128;
+        if carried_in > 0 {
+            let rollover = Self::rollover(&env, &round.token);
+            Self::set_rollover(&env, &round.token, rollover + carried_in);
+        }
+        
+        Self::remove_active_round(&env, round_id);
+        env.storage().instance().extend_ttl(10000, 10000);
+        
+        RoundCancelled {
+            round_id,
+            participants_count: round.participants_count,
+        }.publish(&env);
+        
+        log!(&env, "Round {} cancelled with {} participants", round_id, round.participants_count);
+        Ok(())
+    }
+    
+    // Claim back the ticket price of every ticket held in a cancelled round,
+    // along with the bonds of tickets that were not revealed
+    pub fn claim_refund(env: Env, round_id: u64, participant: Address) -> Result<i128, LotteryError> {
+        participant.require_auth();
+        
+        let round = Self::require_round(&env, round_id)?;
+        
+        if !round.cancelled {
+            log!(&env, "Round is not cancelled!");
+            return Err(LotteryError::RoundNotCancelled);
+        }
+        
+        let refund_key = RefundBook::Refunded(round_id, participant.clone());
+        if env.storage().persistent().has(&refund_key) {
+            log!(&env, "Refund already claimed!");
+            return Err(LotteryError::RefundAlreadyClaimed);
+        }
+        
+        let count_key = ParticipantMap::TicketCount(round_id, participant.clone());
+        let ticket_count: u64 = Self::load_entry(&env, &count_key).unwrap_or(0);
+        if ticket_count == 0 {
+            log!(&env, "Participant holds no tickets in this round!");
+            return Err(LotteryError::NothingToRefund);
+        }
+        
+        // Mark as refunded before paying out
+        Self::save_entry(&env, &refund_key, &true);
+        
+        // Bonds of revealed tickets were already handed back by reveal_seed
+        let revealed: u64 = Self::load_entry(&env, &ParticipantMap::RevealCount(round_id, participant.clone())).unwrap_or(0);
+        let amount = round.ticket_price * ticket_count as i128
+            + Self::ticket_bond(&round) * (ticket_count - revealed) as i128;
+        let token_client = Self::token_client(&env, &round);
+        token_client.transfer(&env.current_contract_address(), &participant, &amount);
+        
+        RefundClaimed {
+            round_id,
+            participant,
+            amount,
+        }.publish(&env);
+        
+        log!(&env, "Refund of {} paid for Round {}", amount, round_id);
+        Ok(amount)
+    }
+    
+    // Helper function to drop a finalized or cancelled round from the active set
+    fn remove_active_round(env: &Env, round_id: u64) {
+        let mut active_rounds = Self::active_rounds(env.clone());
+        if let Some(position) = active_rounds.first_index_of(round_id) {
+            active_rounds.remove(position);
+            env.storage().instance().set(&ACTIVE_ROUNDS, &active_rounds);
+        }
+    }
+    
+    // View the IDs of every round that is not yet finalized or cancelled, oldest first
+    pub fn active_rounds(env: Env) -> Vec<u64> {
+        env.storage().instance().get(&ACTIVE_ROUNDS).unwrap_or(Vec::new(&env))
+    }
+    
+    // View the transcript of a finalized round's draw, or None if the round
+    // has not been drawn or was finalized before draws were recorded
+    pub fn get_draw(env: Env, round_id: u64) -> Option<DrawRecord> {
+        Self::load_entry(&env, &DrawBook::Draw(round_id))
+    }
+    
+    // View up to `limit` of a round's reveals in the order they were made,
+    // skipping the first `start`. Each step carries the seed chain up to and
+    // including that reveal. Rounds whose reveals began under the schema
+    // version 1 build have no steps.
+    pub fn get_reveals(env: Env, round_id: u64, start: u64, limit: u32) -> Vec<RevealStep> {
+        let revealed = Self::get_round(env.clone(), round_id).map_or(0, |round| round.revealed_count);
+        let mut page = Vec::new(&env);
+        for n in Self::page_range(start, limit, revealed) {
+            if let Some(step) = Self::load_entry(&env, &RevealBook::Step(round_id, n)) {
+                page.push_back(step);
+            }
+        }
+        page
+    }
+    
+    // Check a round's draw against the contract's own storage. This is a
+    // consistency check, not a proof: the seed chain it starts from was built
+    // by reveal_seed, which checked each seed against its commit as it was
+    // revealed, and is not re-derived from every reveal, which would not fit
+    // in one call for a large round. From that chain and the record's other
+    // inputs it recomputes the final hash and the winning tickets, then
+    // checks the winners own those tickets and that every revealed winning
+    // ticket's seed still opens its commit. Returns false on any mismatch.
+    // Re-deriving the chain itself is left to off-chain verifiers, from
+    // get_reveals and get_ticket.
+    pub fn verify_draw(env: Env, round_id: u64) -> Result<bool, LotteryError> {
+        let round = Self::require_round(&env, round_id)?;
+        let record = Self::get_draw(env.clone(), round_id).ok_or(LotteryError::DrawNotRecorded)?;
+        
+        let tiers = round.prize_tiers.len();
+        let revealed_only = Self::uses_reveals(&round) && round.reveal_policy == RevealPolicy::Exclude;
+        let matches_round = record.entropy_mode == round.entropy_mode
+            && record.revealed_only == revealed_only
+            && record.eligible_count == Self::eligible_tickets(&round)
+            && record.eligible_count >= tiers as u64;
+        if !matches_round {
+            return Ok(false);
+        }
+        
+        let Some(combined_hash) = Self::seed_hash(&env, &round) else {
+            return Ok(false);
+        };
+        let final_hash = randomness::final_hash(&env, &combined_hash, record.ledger_sequence, record.prng_output.as_ref());
+        let slots = randomness::draw_slots(&env, &final_hash, record.eligible_count, tiers);
+        let Some(winning_tickets) = Self::slots_to_tickets(&env, &round, &slots, revealed_only) else {
+            return Ok(false);
+        };
+        
+        let consistent = record.reveal_count == round.revealed_count
+            && combined_hash == record.combined_hash
+            && final_hash == record.final_hash
+            && winning_tickets == record.winning_tickets
+            && winning_tickets == round.winning_tickets
+            && winning_tickets.len() == round.winners.len();
+        if !consistent {
+            return Ok(false);
+        }
+        
+        for (ticket_index, winner) in winning_tickets.iter().zip(round.winners.iter()) {
+            let owner: Option<Address> = Self::load_entry(&env, &ParticipantBook::Participant(round_id, ticket_index));
+            if owner.as_ref() != Some(&winner) {
+                return Ok(false);
+            }
+            let seed: Option<Bytes> = Self::load_entry(&env, &RevealBook::Reveal(round_id, ticket_index));
+            match seed {
+                Some(seed) => {
+                    let commit: Option<BytesN<32>> = Self::load_entry(&env, &CommitBook::Commit(round_id, ticket_index));
+                    if commit != Some(Self::commit_hash(&env, round.commit_scheme, round_id, &winner, &seed)) {
+                        return Ok(false);
+                    }
+                }
+                // Under Exclude only revealed tickets could have been drawn
+                None if revealed_only => return Ok(false),
+                None => {}
+            }
+        }
+        Ok(true)
+    }
+    
+    // Helper function to turn drawn slots into ticket indexes, or None if a
+    // slot is past the round's reveals
+    fn slots_to_tickets(env: &Env, round: &LotteryRound, slots: &Vec<u64>, revealed_only: bool) -> Option<Vec<u64>> {
+        let mut tickets = Vec::new(env);
+        for slot in slots.iter() {
+            if revealed_only {
+                tickets.push_back(Self::revealed_ticket(env, round, slot)?);
+            } else {
+                tickets.push_back(slot);
+            }
+        }
+        Some(tickets)
+    }
+    
+    // View how many rounds have been created; round IDs run from 1 to this
+    pub fn round_count(env: Env) -> u64 {
+        env.storage().instance().get(&ROUND_COUNT).unwrap_or(0)
+    }
+    
+    // View the lifecycle phase of a round at the current ledger time, or None if it does not exist
+    pub fn round_status(env: Env, round_id: u64) -> Option<RoundStatus> {
+        let round = Self::get_round(env.clone(), round_id)?;
+        Some(Self::status_of(&env, &round))
+    }
+    
+    // View the rounds among IDs start + 1 to start + limit, optionally only
+    // those in one phase. At most MAX_PAGE_SIZE IDs are looked at per call,
+    // so a filtered page can be short without being the last.
+    pub fn list_rounds(env: Env, start: u64, limit: u32, status_filter: Option<RoundStatus>) -> Vec<LotteryRound> {
+        let mut page = Vec::new(&env);
+        for round_id in Self::page_range(start, limit, Self::round_count(env.clone())) {
+            if let Some(round) = Self::get_round(env.clone(), round_id) {
+                if status_filter.is_none_or(|status| Self::status_of(&env, &round) == status) {
+                    page.push_back(round);
+                }
+            }
+        }
+        page
+    }
+    
+    // Helper function to derive a round's phase from its flags and deadlines
+    fn status_of(env: &Env, round: &LotteryRound) -> RoundStatus {
+        let current_time = env.ledger().timestamp();
+        if round.cancelled {
+            RoundStatus::Cancelled
+        } else if round.finalized {
+            RoundStatus::Finalized
+        } else if current_time < round.end_time {
+            RoundStatus::Sale
+        } else if current_time < round.reveal_deadline {
+            RoundStatus::Reveal
+        } else {
+            RoundStatus::Finalizable
+        }
+    }
+    
+    // View the active rounds that are still selling tickets
+    pub fn open_rounds(env: Env) -> Vec<LotteryRound> {
+        let current_time = env.ledger().timestamp();
+        let mut open = Vec::new(&env);
+        for round_id in Self::active_rounds(env.clone()).iter() {
+            if let Some(round) = Self::get_round(env.clone(), round_id) {
+                if current_time < round.end_time {
+                    open.push_back(round);
+                }
+            }
+        }
+        open
+    }
+    
+    // View the owners of up to `limit` tickets in ticket order, skipping the first `start`
+    pub fn get_participants(env: Env, round_id: u64, start: u64, limit: u32) -> Vec<Address> {
+        let sold = Self::get_round(env.clone(), round_id).map_or(0, |round| round.participants_count);
+        let mut page = Vec::new(&env);
+        for ticket_index in Self::page_range(start, limit, sold) {
+            page.push_back(Self::load_entry(&env, &ParticipantBook::Participant(round_id, ticket_index)).unwrap());
+        }
+        page
+    }
+    
+    // View a ticket's owner, commit and revealed seed, or None if it was never sold
+    pub fn get_ticket(env: Env, round_id: u64, index: u64) -> Option<Ticket> {
+        let owner: Address = Self::load_entry(&env, &ParticipantBook::Participant(round_id, index))?;
+        Some(Ticket {
+            index,
+            owner,
+            commit: Self::load_entry(&env, &CommitBook::Commit(round_id, index))?,
+            seed: Self::load_entry(&env, &RevealBook::Reveal(round_id, index)),
+        })
+    }
+    
+    // View whether an address holds tickets in a round and has revealed all of them
+    pub fn has_revealed(env: Env, round_id: u64, addr: Address) -> bool {
+        let held: u64 = Self::load_entry(&env, &ParticipantMap::TicketCount(round_id, addr.clone())).unwrap_or(0);
+        let revealed: u64 = Self::load_entry(&env, &ParticipantMap::RevealCount(round_id, addr)).unwrap_or(0);
+        held > 0 && revealed == held
+    }
+    
+    // View the commit of an address's first ticket, or None if it holds no
+    // tickets. Holders of several tickets can read the others through
+    // tickets_of and get_ticket.
+    pub fn get_commit(env: Env, round_id: u64, addr: Address) -> Result<Option<BytesN<32>>, LotteryError> {
+        let held: u64 = Self::load_entry(&env, &ParticipantMap::TicketCount(round_id, addr.clone())).unwrap_or(0);
+        if held == 0 {
+            return Ok(None);
+        }
+        let ticket_index = Self::owned_ticket(&env, round_id, &addr, 1)?;
+        Ok(Self::load_entry(&env, &CommitBook::Commit(round_id, ticket_index)))
+    }
+    
+    // View up to `limit` of an address's ticket indexes in purchase order, skipping the first `start`
+    pub fn tickets_of(env: Env, round_id: u64, addr: Address, start: u64, limit: u32) -> Result<Vec<u64>, LotteryError> {
+        let held: u64 = Self::load_entry(&env, &ParticipantMap::TicketCount(round_id, addr.clone())).unwrap_or(0);
+        let mut page = Vec::new(&env);
+        for n in Self::page_range(start, limit, held) {
+            page.push_back(Self::owned_ticket(&env, round_id, &addr, n)?);
+        }
+        Ok(page)
+    }
+    
+    // Helper function to look up the index of an address's n-th ticket.
+    // Builds before the owner index existed did not write it, so their
+    // tickets fail with TicketsNotIndexed rather than go missing from a
+    // page; get_participants still lists them.
+    fn owned_ticket(env: &Env, round_id: u64, addr: &Address, n: u64) -> Result<u64, LotteryError> {
+        Self::load_entry(env, &ParticipantMap::Ticket(round_id, addr.clone(), n)).ok_or_else(|| {
+            log!(env, "Ticket was bought before tickets were indexed by owner!");
+            LotteryError::TicketsNotIndexed
+        })
+    }
+    
+    // View an address's lifetime totals, all zero if it never bought a ticket
+    pub fn player_stats(env: Env, addr: Address) -> PlayerStats {
+        Self::load_entry(&env, &PlayerBook::Stats(addr)).unwrap_or(PlayerStats {
+            rounds_played: 0,
+            tickets_bought: 0,
+            total_spent: Map::new(&env),
+            wins: 0,
+            total_winnings: Map::new(&env),
+        })
+    }
+    
+    // View up to `limit` IDs of rounds an address bought tickets in, oldest first, skipping the first `start`
+    pub fn player_rounds(env: Env, addr: Address, start: u64, limit: u32) -> Vec<u64> {
+        let played = Self::player_stats(env.clone(), addr.clone()).rounds_played;
+        let mut page = Vec::new(&env);
+        for n in Self::page_range(start, limit, played) {
+            page.push_back(Self::load_entry(&env, &PlayerBook::Round(addr.clone(), n)).unwrap());
+        }
+        page
+    }
+    
+    // Helper function to turn (start, limit) into 1-based positions among `total`,
+    // capped at MAX_PAGE_SIZE entries
+    fn page_range(start: u64, limit: u32, total: u64) -> core::ops::RangeInclusive<u64> {
+        let last = start.saturating_add(limit.min(MAX_PAGE_SIZE) as u64).min(total);
+        start.saturating_add(1)..=last
+    }
+    
+    // View a round, or None if no round with this ID exists
+    pub fn get_round(env: Env, round_id: u64) -> Option<LotteryRound> {
+        let stored: Map<Symbol, Val> = Self::load_entry(&env, &RoundBook::Round(round_id))?;
+        
+        // Rounds migrate has not rewritten yet are still in the version 1
+        // layout, which has no keeper_bps field. They take the keeper reward
+        // in force until migrate writes it into the round.
+        if stored.contains_key(Symbol::new(&env, "keeper_bps")) {
+            return Some(LotteryRound::try_from_val(&env, &stored.to_val()).unwrap());
+        }
+        let keeper_bps = Self::keeper_reward(env.clone());
+        Some(legacy::LotteryRoundV1::try_from_val(&env, &stored.to_val()).unwrap().into_current(keeper_bps))
+    }
+    
+    // Helper function to load a round, failing with RoundNotFound if it does
+    // not exist. Every entrypoint that acts on a round comes through here, so
+    // the round stays live while anyone is still using it.
+    fn require_round(env: &Env, round_id: u64) -> Result<LotteryRound, LotteryError> {
+        let round = Self::get_round(env.clone(), round_id).ok_or_else(|| {
+            log!(env, "Lottery round not found!");
+            LotteryError::RoundNotFound
+        })?;
+        env.storage().persistent().extend_ttl(&RoundBook::Round(round_id), ROUND_TTL_THRESHOLD, ROUND_TTL_EXTEND);
+        Ok(round)
+    }
+    
+    // View lottery round details. Kept for existing clients: a missing round
+    // comes back zeroed with round_id 0, so new code should use get_round.
+    pub fn view_round(env: Env, round_id: u64) -> LotteryRound {
+        Self::get_round(env.clone(), round_id).unwrap_or(LotteryRound {
             round_id: 0,
+            token: env.current_contract_address(),
             ticket_price: 0,
             total_pool: 0,
             participants_count: 0,
             is_active: false,
-            winner: None,
+            winners: Vec::new(&env),
+            winning_tickets: Vec::new(&env),
             end_time: 0,
             reveal_deadline: 0,
             finalized: false,
             allow_multiple: false,
+            min_participants: 0,
+            cancelled: false,
+            prize_tiers: Vec::new(&env),
+            prizes: Vec::new(&env),
+            prizes_claimed: Vec::new(&env),
+            prize_amount: 0,
+            claim_deadline: 0,
+            prize_expired: false,
+            fee_bps: 0,
+            fee_amount: 0,
+            keeper_bps: 0,
+            reveal_policy: RevealPolicy::Exclude,
+            revealed_count: 0,
+            forfeited_bonds: 0,
+            entropy_mode: EntropyMode::CommitReveal,
+            commit_scheme: CommitScheme::V2,
         })
     }
 }
@@ -0,0 +1,13 @@
+use soroban_sdk::{contractclient, Address, BytesN, Env};
+
+// Interface a randomness provider contract implements to serve Provider
+// rounds. request_randomness records the request and returns; the provider
+// answers in a later transaction by calling fulfill_randomness(round_id,
+// signature) on `consumer`, with its ed25519 signature over request_id made
+// by the key registered alongside it. The round's randomness is
+// sha256(signature). Calling back from inside request_randomness would
+// re-enter the lottery, which Soroban rejects.
+#[contractclient(name = "RandomnessProviderClient")]
+pub trait RandomnessProviderInterface {
+    fn request_randomness(env: Env, consumer: Address, round_id: u64, request_id: BytesN<32>);
+}
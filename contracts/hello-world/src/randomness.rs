@@ -0,0 +1,101 @@
+use soroban_sdk::{Bytes, BytesN, Env, Vec};
+
+// Fold more input into a running hash: sha256(acc || input)
+pub(crate) fn chain(env: &Env, acc: &BytesN<32>, input: &Bytes) -> BytesN<32> {
+    let mut hash_input = Bytes::from_array(env, &acc.to_array());
+    hash_input.append(input);
+    env.crypto().sha256(&hash_input).into()
+}
+
+// Final hash of a draw from the inputs its entropy mode uses: the ledger
+// sequence is chained onto the seed hash, then the PRNG output is chained
+// on too, or used alone when there is no sequence
+pub(crate) fn final_hash(env: &Env, seed_hash: &BytesN<32>, ledger_sequence: Option<u32>, prng_output: Option<&BytesN<32>>) -> BytesN<32> {
+    let mut hash = seed_hash.clone();
+    if let Some(sequence) = ledger_sequence {
+        hash = chain(env, &hash, &Bytes::from_slice(env, &sequence.to_be_bytes()));
+    }
+    match (ledger_sequence, prng_output) {
+        (Some(_), Some(prng_output)) => chain(env, &hash, &Bytes::from_array(env, &prng_output.to_array())),
+        (None, Some(prng_output)) => prng_output.clone(),
+        (_, None) => hash,
+    }
+}
+
+// 32 bytes from the Soroban PRNG. The host seeds it per transaction, so it
+// is unknown to participants in advance but not to the network's validators.
+pub(crate) fn prng_output(env: &Env) -> BytesN<32> {
+    env.prng().gen()
+}
+
+// Hash for draw n of a round: the final hash itself for the first draw,
+// sha256(final_hash || n) for the ones after it
+pub(crate) fn draw_hash(env: &Env, final_hash: &BytesN<32>, draw: u32) -> BytesN<32> {
+    if draw == 0 {
+        final_hash.clone()
+    } else {
+        chain(env, final_hash, &Bytes::from_slice(env, &draw.to_be_bytes()))
+    }
+}
+
+// Map a 64-bit word to [0, bound) without modulo bias, or None if the word
+// falls in the short tail that would favour the low values. Words from
+// `threshold` up cover a whole number of multiples of `bound`.
+pub(crate) fn reduce(word: u64, bound: u64) -> Option<u64> {
+    let threshold = bound.wrapping_neg() % bound;
+    if word >= threshold {
+        Some(word % bound)
+    } else {
+        None
+    }
+}
+
+// Derive a uniform index in [0, bound) from a hash by rejection sampling.
+// Each of the hash's four big-endian words is tried in turn; if all are
+// rejected the hash is replaced by sha256(hash || n) on the n-th retry and
+// sampling continues. A word is rejected with probability below
+// bound / 2^64, so in practice the first word is used.
+pub(crate) fn uniform_index(env: &Env, hash: &BytesN<32>, bound: u64) -> u64 {
+    assert!(bound > 0, "bound must be positive");
+    
+    let mut hash = hash.clone();
+    let mut extension = 0u32;
+    loop {
+        let bytes = hash.to_array();
+        for chunk in bytes.chunks_exact(8) {
+            let mut word = [0u8; 8];
+            word.copy_from_slice(chunk);
+            if let Some(index) = reduce(u64::from_be_bytes(word), bound) {
+                return index;
+            }
+        }
+        extension += 1;
+        hash = chain(env, &hash, &Bytes::from_slice(env, &extension.to_be_bytes()));
+    }
+}
+
+// Draw `count` distinct slots out of 1..=eligible, in draw order. Draw n
+// picks uniformly among the slots not drawn yet, using draw_hash(n), and
+// steps over earlier winners so no slot can win twice.
+pub(crate) fn draw_slots(env: &Env, final_hash: &BytesN<32>, eligible: u64, count: u32) -> Vec<u64> {
+    let mut slots = Vec::new(env);
+    let mut drawn_sorted: Vec<u64> = Vec::new(env);
+    for draw in 0..count {
+        let remaining = eligible - draw as u64;
+        let mut slot = uniform_index(env, &draw_hash(env, final_hash, draw), remaining) + 1;
+        
+        // Step over slots that already won, keeping the list sorted
+        let mut insert_at = drawn_sorted.len();
+        for (position, drawn) in drawn_sorted.iter().enumerate() {
+            if drawn <= slot {
+                slot += 1;
+            } else {
+                insert_at = position as u32;
+                break;
+            }
+        }
+        drawn_sorted.insert(insert_at, slot);
+        slots.push_back(slot);
+    }
+    slots
+}
@@ -1,7 +1,63 @@
 #![cfg(test)]
+#![allow(clippy::needless_range_loop, clippy::bool_assert_comparison)]
+extern crate std;
 
 use super::*;
-use soroban_sdk::{testutils::{Address as _, Ledger}, token, Address, Env, Vec, Bytes, BytesN};
+use soroban_sdk::{events::Event, xdr::{self, ToXdr}, testutils::{storage::Persistent as _, Address as _, EnvTestConfig, Events, Ledger}, token, vec, Address, Env, IntoVal, Map, Symbol, Vec, Val, Bytes, BytesN};
+
+// Build of the contract at schema version 1, the older layout the upgrade
+// tests start from. The imported client speaks that build's interface.
+mod lottery_v1 {
+    soroban_sdk::contractimport!(file = "test_wasms/lottery_v1.wasm");
+}
+
+// Randomness provider stand-in for Provider rounds. It records requests and,
+// like a VRF oracle's contract, relays a value signed off-chain back to the
+// lottery in a later transaction.
+#[contract]
+pub struct MockRandomnessProvider;
+
+#[contractimpl]
+impl RandomnessProviderInterface for MockRandomnessProvider {
+    fn request_randomness(env: Env, consumer: Address, round_id: u64, request_id: BytesN<32>) {
+        env.storage().instance().set(&round_id, &(consumer, request_id));
+    }
+}
+
+#[contractimpl]
+impl MockRandomnessProvider {
+    pub fn last_request(env: Env, round_id: u64) -> Option<(Address, BytesN<32>)> {
+        env.storage().instance().get(&round_id)
+    }
+    
+    pub fn fulfill(env: Env, round_id: u64, signature: BytesN<64>) -> Vec<Address> {
+        let (consumer, _): (Address, BytesN<32>) = env.storage().instance().get(&round_id).unwrap();
+        LotteryContractClient::new(&env, &consumer).fulfill_randomness(&round_id, &signature)
+    }
+}
+
+// Finalizes a round from a contract and reverts unless it won, the way an
+// attacker would retry a draw made inside its own transaction until it wins
+#[contract]
+pub struct WinOrRevert;
+
+#[contractimpl]
+impl WinOrRevert {
+    pub fn attack(env: Env, lottery: Address, round_id: u64) -> Vec<Address> {
+        let me = env.current_contract_address();
+        let winners = LotteryContractClient::new(&env, &lottery).finalize_round(&me, &round_id);
+        if !winners.contains(&me) {
+            panic!("lost, try again next ledger");
+        }
+        winners
+    }
+}
+
+// Helper function to sign a provider answer: ed25519 over request_id
+fn sign_request(env: &Env, key: &ed25519_dalek::SigningKey, request_id: &BytesN<32>) -> BytesN<64> {
+    use ed25519_dalek::Signer;
+    BytesN::from_array(env, &key.sign(&request_id.to_array()).to_bytes())
+}
 
 // Helper function to create test token
 fn create_token_contract<'a>(env: &Env, admin: &Address) -> (token::Client<'a>, token::StellarAssetClient<'a>) {
@@ -12,8 +68,19 @@ fn create_token_contract<'a>(env: &Env, admin: &Address) -> (token::Client<'a>,
     )
 }
 
-// Helper function to create commit hash: sha256(seed || address || round_id)
-fn create_commit_hash(env: &Env, seed: &Bytes, address: &Address, round_id: u64) -> BytesN<32> {
+// Helper function to create commit hash:
+// sha256("lottery-commit-v2" || contract XDR || round_id || address XDR || seed)
+fn create_commit_hash(env: &Env, contract_id: &Address, seed: &Bytes, address: &Address, round_id: u64) -> BytesN<32> {
+    let mut hash_input = Bytes::from_slice(env, b"lottery-commit-v2");
+    hash_input.append(&contract_id.clone().to_xdr(env));
+    hash_input.append(&Bytes::from_slice(env, &round_id.to_be_bytes()));
+    hash_input.append(&address.clone().to_xdr(env));
+    hash_input.append(seed);
+    env.crypto().sha256(&hash_input).into()
+}
+
+// Helper function to create a CommitScheme::V1 commit hash: sha256(seed || address || round_id)
+fn create_v1_commit_hash(env: &Env, seed: &Bytes, address: &Address, round_id: u64) -> BytesN<32> {
     let mut hash_input = Bytes::new(env);
     hash_input.append(seed);
     let addr_bytes: Bytes = address.to_string().to_bytes();
@@ -22,6 +89,37 @@ fn create_commit_hash(env: &Env, seed: &Bytes, address: &Address, round_id: u64)
     env.crypto().sha256(&hash_input).into()
 }
 
+// Helper function to build a single-tier, commit-reveal round config
+fn round_config(env: &Env, token: &Address, ticket_price: i128, sale_hours: u64, allow_multiple: bool) -> RoundConfig {
+    RoundConfig {
+        token: token.clone(),
+        ticket_price,
+        sale_duration: sale_hours * 3600,
+        reveal_duration: 24 * 3600,
+        allow_multiple,
+        min_participants: 0,
+        prize_tiers: vec![env, 10_000u32],
+        reveal_policy: RevealPolicy::Exclude,
+        entropy_mode: EntropyMode::CommitReveal,
+    }
+}
+
+// Helper function to collect the events published by one contract, dropping token events
+fn contract_events(env: &Env, contract_id: &Address) -> Vec<(Address, Vec<Val>, Val)> {
+    let mut events = Vec::new(env);
+    for event in env.events().all().iter() {
+        if &event.0 == contract_id {
+            events.push_back(event);
+        }
+    }
+    events
+}
+
+// Helper function to turn an expected contract event into the shape returned by env.events()
+fn as_published(env: &Env, contract_id: &Address, event: &impl Event) -> (Address, Vec<Val>, Val) {
+    (contract_id.clone(), event.topics(env), event.data(env))
+}
+
 #[test]
 fn test_init_admin() {
     let env = Env::default();
@@ -38,12 +136,11 @@ fn test_init_admin() {
     client.init_admin(&admin, &token.address);
     
     // Verify we can create a round (only admin can do this)
-    let round_id = client.create_round(&100, &24, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
     assert_eq!(round_id, 1);
 }
 
 #[test]
-#[should_panic(expected = "Contract already initialized")]
 fn test_init_admin_twice_fails() {
     let env = Env::default();
     env.mock_all_auths();
@@ -58,8 +155,9 @@ fn test_init_admin_twice_fails() {
     // Initialize contract
     client.init_admin(&admin, &token.address);
     
-    // Try to initialize again - should panic
-    client.init_admin(&admin, &token.address);
+    // Try to initialize again - should fail
+    let result = client.try_init_admin(&admin, &token.address);
+    assert_eq!(result, Err(Ok(LotteryError::AlreadyInitialized)));
 }
 
 #[test]
@@ -79,7 +177,7 @@ fn test_create_round() {
     // Create a round
     let ticket_price = 100;
     let duration_hours = 24;
-    let round_id = client.create_round(&ticket_price, &duration_hours, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, duration_hours, false));
     
     assert_eq!(round_id, 1);
     
@@ -93,8 +191,7 @@ fn test_create_round() {
 }
 
 #[test]
-#[should_panic(expected = "Cannot create new round. Active round exists")]
-fn test_cannot_create_multiple_active_rounds() {
+fn test_active_round_limit() {
     let env = Env::default();
     env.mock_all_auths();
     
@@ -107,11 +204,22 @@ fn test_cannot_create_multiple_active_rounds() {
     
     client.init_admin(&admin, &token.address);
     
-    // Create first round
-    client.create_round(&100, &24, &false);
-    
-    // Try to create second round while first is active - should panic
-    client.create_round(&200, &48, &false);
+    // Rounds can run side by side up to the limit
+    for _ in 0..20 {
+        client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
+    }
+    assert_eq!(client.active_rounds().len(), 20);
+    
+    let result = client.try_create_round(&admin, &round_config(&env, &token.address, 200, 48, false));
+    assert_eq!(result, Err(Ok(LotteryError::TooManyActiveRounds)));
+    
+    // Closing a round frees a slot
+    client.cancel_round(&1);
+    client.create_round(&admin, &round_config(&env, &token.address, 200, 48, false));
+    let active_rounds = client.active_rounds();
+    assert_eq!(active_rounds.len(), 20);
+    assert!(!active_rounds.contains(1));
+    assert_eq!(active_rounds.last().unwrap(), 21);
 }
 
 #[test]
@@ -135,11 +243,11 @@ fn test_buy_ticket() {
     
     // Create a round
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &24, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 24, false));
     
     // Buy ticket with commit hash
     let seed = Bytes::from_slice(&env, b"test_seed_123");
-    let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+    let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &ticket_price, &commit_hash);
     
     // Verify round updated
@@ -153,7 +261,6 @@ fn test_buy_ticket() {
 }
 
 #[test]
-#[should_panic(expected = "Incorrect payment amount")]
 fn test_buy_ticket_wrong_amount() {
     let env = Env::default();
     env.mock_all_auths();
@@ -170,12 +277,13 @@ fn test_buy_ticket_wrong_amount() {
     
     client.init_admin(&admin, &token.address);
     
-    let round_id = client.create_round(&100, &24, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
     
-    // Try to buy ticket with wrong amount - should panic
+    // Try to buy ticket with wrong amount - should fail
     let seed = Bytes::from_slice(&env, b"test_seed");
-    let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
-    client.buy_ticket(&round_id, &participant, &50, &commit_hash);
+    let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    let result = client.try_buy_ticket(&round_id, &participant, &50, &commit_hash);
+    assert_eq!(result, Err(Ok(LotteryError::WrongAmount)));
 }
 
 #[test]
@@ -193,7 +301,7 @@ fn test_multiple_participants() {
     client.init_admin(&admin, &token.address);
     
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &24, &true);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 24, true));
     
     // Create multiple participants
     let seed_strings = [b"seed_0", b"seed_1", b"seed_2", b"seed_3", b"seed_4"];
@@ -201,7 +309,7 @@ fn test_multiple_participants() {
         let participant = Address::generate(&env);
         token_admin_client.mint(&participant, &1000);
         let seed = Bytes::from_slice(&env, seed_strings[i]);
-        let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+        let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
         client.buy_ticket(&round_id, &participant, &ticket_price, &commit_hash);
     }
     
@@ -227,7 +335,7 @@ fn test_commit_reveal_finalize() {
     
     // Create round with 1 hour duration
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 1, false));
     
     // Add participants with commits
     let mut participants = Vec::new(&env);
@@ -237,7 +345,7 @@ fn test_commit_reveal_finalize() {
         let participant = Address::generate(&env);
         token_admin_client.mint(&participant, &1000);
         let seed = Bytes::from_slice(&env, seed_strings[i]);
-        let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+        let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
         client.buy_ticket(&round_id, &participant, &ticket_price, &commit_hash);
         participants.push_back(participant.clone());
         seeds.push_back(seed);
@@ -252,7 +360,7 @@ fn test_commit_reveal_finalize() {
     for i in 0..3 {
         let participant = participants.get(i as u32).unwrap();
         let seed = seeds.get(i as u32).unwrap();
-        client.reveal_seed(&round_id, &participant, &seed);
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
     }
     
     // Fast forward past reveal deadline
@@ -261,7 +369,7 @@ fn test_commit_reveal_finalize() {
     });
     
     // Finalize round and draw winner
-    let winner = client.finalize_round(&round_id);
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
     
     // Verify winner is one of the participants
     assert!(participants.contains(&winner));
@@ -270,7 +378,13 @@ fn test_commit_reveal_finalize() {
     let round = client.view_round(&round_id);
     assert_eq!(round.is_active, false);
     assert_eq!(round.finalized, true);
-    assert!(round.winner.is_some());
+    assert_eq!(round.winners.len(), 1);
+    
+    assert_eq!(round.prize_amount, 300);
+    
+    // Prize stays in the contract until the winner claims it
+    assert_eq!(token.balance(&winner), 900);
+    assert_eq!(client.claim_prize(&round_id, &winner), 300);
     
     // Verify winner received tokens (they also bought a ticket)
     // Winner had 1000, spent 100 on ticket, received 300 prize = 1200
@@ -278,7 +392,6 @@ fn test_commit_reveal_finalize() {
 }
 
 #[test]
-#[should_panic(expected = "Reveal deadline has not passed yet")]
 fn test_cannot_finalize_before_reveal_deadline() {
     let env = Env::default();
     env.mock_all_auths();
@@ -292,13 +405,13 @@ fn test_cannot_finalize_before_reveal_deadline() {
     
     client.init_admin(&admin, &token.address);
     
-    let round_id = client.create_round(&100, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
     
     // Add a participant
     let participant = Address::generate(&env);
     token_admin_client.mint(&participant, &1000);
     let seed = Bytes::from_slice(&env, b"seed");
-    let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+    let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &100, &commit_hash);
     
     // Fast forward past end time but not reveal deadline
@@ -306,12 +419,12 @@ fn test_cannot_finalize_before_reveal_deadline() {
         li.timestamp += 3600 + 1;
     });
     
-    // Try to finalize before reveal deadline - should panic
-    client.finalize_round(&round_id);
+    // Try to finalize before reveal deadline - should fail
+    let result = client.try_finalize_round(&admin, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::RevealDeadlineNotPassed)));
 }
 
 #[test]
-#[should_panic(expected = "No participants in this round")]
 fn test_cannot_finalize_no_participants() {
     let env = Env::default();
     env.mock_all_auths();
@@ -325,15 +438,16 @@ fn test_cannot_finalize_no_participants() {
     
     client.init_admin(&admin, &token.address);
     
-    let round_id = client.create_round(&100, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
     
     // Fast forward time past reveal deadline
     env.ledger().with_mut(|li| {
         li.timestamp += 3600 + (24 * 3600) + 1;
     });
     
-    // Try to finalize with no participants - should panic
-    client.finalize_round(&round_id);
+    // Try to finalize with no participants - should fail
+    let result = client.try_finalize_round(&admin, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::NoParticipants)));
 }
 
 #[test]
@@ -350,12 +464,12 @@ fn test_reveal_seed() {
     
     client.init_admin(&admin, &token.address);
     
-    let round_id = client.create_round(&100, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
     
     let participant = Address::generate(&env);
     token_admin_client.mint(&participant, &1000);
     let seed = Bytes::from_slice(&env, b"my_secret_seed");
-    let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+    let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
     
     client.buy_ticket(&round_id, &participant, &100, &commit_hash);
     
@@ -365,11 +479,10 @@ fn test_reveal_seed() {
     });
     
     // Reveal seed - should succeed
-    client.reveal_seed(&round_id, &participant, &seed);
+    client.reveal_seed(&round_id, &participant, &1, &seed);
 }
 
 #[test]
-#[should_panic(expected = "Seed does not match commit")]
 fn test_reveal_wrong_seed() {
     let env = Env::default();
     env.mock_all_auths();
@@ -383,23 +496,38 @@ fn test_reveal_wrong_seed() {
     
     client.init_admin(&admin, &token.address);
     
-    let round_id = client.create_round(&100, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
     
     let participant = Address::generate(&env);
     token_admin_client.mint(&participant, &1000);
     let seed = Bytes::from_slice(&env, b"my_secret_seed");
-    let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+    let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
     
     client.buy_ticket(&round_id, &participant, &100, &commit_hash);
     
+    // Seeds are capped at 32 bytes, even one committed to correctly
+    let long_seed = Bytes::from_array(&env, &[7u8; 33]);
+    let long_seeder = Address::generate(&env);
+    token_admin_client.mint(&long_seeder, &1000);
+    client.buy_ticket(&round_id, &long_seeder, &100, &create_commit_hash(&env, &contract_id, &long_seed, &long_seeder, round_id));
+    let full_seed = Bytes::from_array(&env, &[7u8; 32]);
+    let full_seeder = Address::generate(&env);
+    token_admin_client.mint(&full_seeder, &1000);
+    client.buy_ticket(&round_id, &full_seeder, &100, &create_commit_hash(&env, &contract_id, &full_seed, &full_seeder, round_id));
+    
     // Fast forward past end_time
     env.ledger().with_mut(|li| {
         li.timestamp += 3600 + 1;
     });
     
-    // Try to reveal wrong seed - should panic
+    // Try to reveal wrong seed - should fail
     let wrong_seed = Bytes::from_slice(&env, b"wrong_seed");
-    client.reveal_seed(&round_id, &participant, &wrong_seed);
+    let result = client.try_reveal_seed(&round_id, &participant, &1, &wrong_seed);
+    assert_eq!(result, Err(Ok(LotteryError::CommitMismatch)));
+    
+    let result = client.try_reveal_seed(&round_id, &long_seeder, &2, &long_seed);
+    assert_eq!(result, Err(Ok(LotteryError::CommitMismatch)));
+    client.reveal_seed(&round_id, &full_seeder, &3, &full_seed);
 }
 
 #[test]
@@ -417,7 +545,7 @@ fn test_finalize_with_missing_reveals() {
     client.init_admin(&admin, &token.address);
     
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 1, false));
     
     // Add 3 participants
     let mut participants = Vec::new(&env);
@@ -427,7 +555,7 @@ fn test_finalize_with_missing_reveals() {
         let participant = Address::generate(&env);
         token_admin_client.mint(&participant, &1000);
         let seed = Bytes::from_slice(&env, seed_strings[i]);
-        let commit_hash = create_commit_hash(&env, &seed, &participant, round_id);
+        let commit_hash = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
         client.buy_ticket(&round_id, &participant, &ticket_price, &commit_hash);
         participants.push_back(participant.clone());
         seeds.push_back(seed);
@@ -442,7 +570,7 @@ fn test_finalize_with_missing_reveals() {
     for i in 0..2 {
         let participant = participants.get(i as u32).unwrap();
         let seed = seeds.get(i as u32).unwrap();
-        client.reveal_seed(&round_id, &participant, &seed);
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
     }
     
     // Fast forward past reveal deadline
@@ -451,7 +579,7 @@ fn test_finalize_with_missing_reveals() {
     });
     
     // Finalize should still work with missing reveals
-    let winner = client.finalize_round(&round_id);
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
     
     // Winner should be one of the participants
     assert!(participants.contains(&winner));
@@ -477,19 +605,19 @@ fn test_allow_multiple_tickets_true() {
     client.init_admin(&admin, &token.address);
     
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &24, &true); // Allow multiple
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 24, true)); // Allow multiple
     
     let participant = Address::generate(&env);
     token_admin_client.mint(&participant, &1000);
     
     // Buy first ticket
     let seed1 = Bytes::from_slice(&env, b"seed1");
-    let commit1 = create_commit_hash(&env, &seed1, &participant, round_id);
+    let commit1 = create_commit_hash(&env, &contract_id, &seed1, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &ticket_price, &commit1);
     
     // Buy second ticket - should succeed
     let seed2 = Bytes::from_slice(&env, b"seed2");
-    let commit2 = create_commit_hash(&env, &seed2, &participant, round_id);
+    let commit2 = create_commit_hash(&env, &contract_id, &seed2, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &ticket_price, &commit2);
     
     // Verify 2 tickets purchased
@@ -499,7 +627,6 @@ fn test_allow_multiple_tickets_true() {
 }
 
 #[test]
-#[should_panic(expected = "Participant already bought a ticket for this round")]
 fn test_allow_multiple_tickets_false() {
     let env = Env::default();
     env.mock_all_auths();
@@ -514,24 +641,24 @@ fn test_allow_multiple_tickets_false() {
     client.init_admin(&admin, &token.address);
     
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &24, &false); // Disallow multiple
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 24, false)); // Disallow multiple
     
     let participant = Address::generate(&env);
     token_admin_client.mint(&participant, &1000);
     
     // Buy first ticket
     let seed1 = Bytes::from_slice(&env, b"seed1");
-    let commit1 = create_commit_hash(&env, &seed1, &participant, round_id);
+    let commit1 = create_commit_hash(&env, &contract_id, &seed1, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &ticket_price, &commit1);
     
-    // Try to buy second ticket - should panic
+    // Try to buy second ticket - should fail
     let seed2 = Bytes::from_slice(&env, b"seed2");
-    let commit2 = create_commit_hash(&env, &seed2, &participant, round_id);
-    client.buy_ticket(&round_id, &participant, &ticket_price, &commit2);
+    let commit2 = create_commit_hash(&env, &contract_id, &seed2, &participant, round_id);
+    let result = client.try_buy_ticket(&round_id, &participant, &ticket_price, &commit2);
+    assert_eq!(result, Err(Ok(LotteryError::DuplicateTicket)));
 }
 
 #[test]
-#[should_panic(expected = "Contract not initialized")]
 fn test_unauthorized_admin_create_round() {
     let env = Env::default();
     env.mock_all_auths();
@@ -539,8 +666,10 @@ fn test_unauthorized_admin_create_round() {
     let contract_id = env.register(LotteryContract, ());
     let client = LotteryContractClient::new(&env, &contract_id);
     
-    // Try to create round without initializing - should panic
-    client.create_round(&100, &24, &false);
+    // Try to create round without initializing - should fail
+    let token = Address::generate(&env);
+    let result = client.try_create_round(&Address::generate(&env), &round_config(&env, &token, 100, 24, false));
+    assert_eq!(result, Err(Ok(LotteryError::NotInitialized)));
 }
 
 #[test]
@@ -560,7 +689,7 @@ fn test_full_lottery_lifecycle() {
     
     // 2. Create round
     let ticket_price = 100;
-    let round_id = client.create_round(&ticket_price, &1, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, ticket_price, 1, false));
     assert_eq!(round_id, 1);
     
     // 3. Multiple participants buy tickets
@@ -572,7 +701,7 @@ fn test_full_lottery_lifecycle() {
         let participant = Address::generate(&env);
         token_admin_client.mint(&participant, &1000);
         let seed = Bytes::from_slice(&env, seed_strings[i]);
-        let commit = create_commit_hash(&env, &seed, &participant, round_id);
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
         client.buy_ticket(&round_id, &participant, &ticket_price, &commit);
         participants.push_back(participant);
         seeds.push_back(seed);
@@ -593,7 +722,7 @@ fn test_full_lottery_lifecycle() {
     for i in 0..3 {
         let participant = participants.get(i as u32).unwrap();
         let seed = seeds.get(i as u32).unwrap();
-        client.reveal_seed(&round_id, &participant, &seed);
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
     }
     
     // 7. Fast forward past reveal deadline
@@ -602,16 +731,17 @@ fn test_full_lottery_lifecycle() {
     });
     
     // 8. Finalize and verify winner
-    let winner = client.finalize_round(&round_id);
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
     assert!(participants.contains(&winner));
     
     // 9. Verify final state
     let final_round = client.view_round(&round_id);
     assert_eq!(final_round.is_active, false);
     assert_eq!(final_round.finalized, true);
-    assert!(final_round.winner.is_some());
+    assert_eq!(final_round.winners.len(), 1);
     
-    // 10. Verify winner received prize
+    // 10. Winner claims and receives the prize
+    client.claim_prize(&round_id, &winner);
     assert_eq!(token.balance(&winner), 1200); // 1000 - 100 + 300
 }
 
@@ -631,7 +761,7 @@ fn test_token_payment_mock() {
     let client = LotteryContractClient::new(&env, &contract_id);
     
     client.init_admin(&admin, &token.address);
-    let round_id = client.create_round(&100, &24, &false);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
     
     // Verify initial balances
     assert_eq!(token.balance(&participant), 1000);
@@ -639,10 +769,3467 @@ fn test_token_payment_mock() {
     
     // Buy ticket
     let seed = Bytes::from_slice(&env, b"seed");
-    let commit = create_commit_hash(&env, &seed, &participant, round_id);
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
     client.buy_ticket(&round_id, &participant, &100, &commit);
     
     // Verify balances after purchase
     assert_eq!(token.balance(&participant), 900);
     assert_eq!(token.balance(&contract_id), 100);
 }
+
+#[test]
+fn test_buy_ticket_round_not_found() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // No round has been created yet
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, 1);
+    let result = client.try_buy_ticket(&1, &participant, &100, &commit);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotFound)));
+}
+
+#[test]
+fn test_cannot_buy_ticket_after_end_time() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    // Fast forward past end time
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    let result = client.try_buy_ticket(&round_id, &participant, &100, &commit);
+    assert_eq!(result, Err(Ok(LotteryError::RoundEnded)));
+    assert_eq!(token.balance(&participant), 1000);
+}
+
+#[test]
+fn test_cannot_reveal_before_end_time() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    client.buy_ticket(&round_id, &participant, &100, &commit);
+    
+    // Sale is still open, so reveals are rejected
+    let result = client.try_reveal_seed(&round_id, &participant, &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::RevealNotStarted)));
+}
+
+#[test]
+fn test_reveal_without_commit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 1;
+    });
+    
+    // Address never bought a ticket
+    let outsider = Address::generate(&env);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let result = client.try_reveal_seed(&round_id, &outsider, &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::NoCommit)));
+}
+
+#[test]
+fn test_round_created_event() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 2, true));
+    let events = contract_events(&env, &contract_id);
+    
+    let round = client.view_round(&round_id);
+    let expected = RoundCreated {
+        round_id,
+        token: token.address.clone(),
+        ticket_price: 100,
+        initial_pool: 0,
+        end_time: round.end_time,
+        reveal_deadline: round.reveal_deadline,
+        allow_multiple: true,
+    };
+    assert_eq!(
+        events,
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+}
+
+#[test]
+fn test_ticket_purchased_event() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, true));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    
+    let seed1 = Bytes::from_slice(&env, b"seed1");
+    let commit1 = create_commit_hash(&env, &contract_id, &seed1, &participant, round_id);
+    client.buy_ticket(&round_id, &participant, &100, &commit1);
+    
+    let seed2 = Bytes::from_slice(&env, b"seed2");
+    let commit2 = create_commit_hash(&env, &contract_id, &seed2, &participant, round_id);
+    client.buy_ticket(&round_id, &participant, &100, &commit2);
+    
+    // Second purchase reports the second ticket slot
+    let expected = TicketPurchased {
+        round_id,
+        buyer: participant.clone(),
+        ticket_index: 2,
+        amount: 100,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+}
+
+#[test]
+fn test_reveal_and_finalize_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let mut participants = Vec::new(&env);
+    let mut seeds = Vec::new(&env);
+    let seed_strings = [b"seed_0", b"seed_1"];
+    for seed_string in seed_strings {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let seed = Bytes::from_slice(&env, seed_string);
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &100, &commit);
+        participants.push_back(participant);
+        seeds.push_back(seed);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 1;
+    });
+    
+    // Only the first participant reveals
+    let revealer = participants.get(0).unwrap();
+    client.reveal_seed(&round_id, &revealer, &1, &seeds.get(0).unwrap());
+    let expected = SeedRevealed {
+        round_id,
+        participant: revealer.clone(),
+        ticket_index: 1,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600 + 1;
+    });
+    
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
+    let events = contract_events(&env, &contract_id);
+    
+    // Entropy hash is sha256(sha256(zero || seed_0) || ledger_sequence)
+    let mut combined_input = Bytes::from_array(&env, &[0u8; 32]);
+    combined_input.append(&seeds.get(0).unwrap());
+    let combined_hash: BytesN<32> = env.crypto().sha256(&combined_input).into();
+    let mut final_input = Bytes::from_array(&env, &combined_hash.to_array());
+    final_input.append(&Bytes::from_slice(&env, &env.ledger().sequence().to_be_bytes()));
+    let entropy_hash: BytesN<32> = env.crypto().sha256(&final_input).into();
+    
+    let winner_index = if participants.get(0).unwrap() == winner { 1 } else { 2 };
+    let inputs = DrawEntropy {
+        round_id,
+        mode: EntropyMode::CommitReveal,
+        seed_hash: Some(combined_hash),
+        ledger_sequence: Some(env.ledger().sequence()),
+        prng_output: None,
+        final_hash: entropy_hash.clone(),
+    };
+    let selected = WinnerSelected {
+        round_id,
+        tier: 0,
+        winner: winner.clone(),
+        winner_index,
+        reveal_count: 1,
+        entropy_hash,
+    };
+    assert_eq!(
+        events,
+        vec![
+            &env,
+            as_published(&env, &contract_id, &inputs),
+            as_published(&env, &contract_id, &selected)
+        ]
+    );
+    
+    client.claim_prize(&round_id, &winner);
+    let paid = PrizePaid {
+        round_id,
+        winner: winner.clone(),
+        tiers: vec![&env, 0],
+        amount: 200,
+        fee_amount: 0,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &paid)]
+    );
+}
+
+// Helper function to carry a test's ledger over to a fresh Env. The test host
+// keeps every entry touched so far in one map, so each call costs more as the
+// ledger grows; a fresh host loads only the entries a call uses, as a network
+// transaction does.
+fn reloaded(env: &Env, contract_id: &Address) -> Env {
+    let snapshot = env.to_snapshot();
+    
+    // Mocked auths draw their nonces from the host's PRNG, so reseed it or
+    // the fresh host would repeat nonces already spent on the ledger
+    let mut prng_seed = [0u8; 32];
+    prng_seed[..8].copy_from_slice(&(snapshot.ledger.ledger_entries.len() as u64).to_be_bytes());
+    
+    let mut fresh = Env::from_snapshot(snapshot);
+    fresh.set_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    fresh.host().set_base_prng_seed(prng_seed).unwrap();
+    fresh.mock_all_auths();
+    fresh.register_at(&moved(&fresh, contract_id), LotteryContract, ());
+    fresh
+}
+
+// Helper function to refer to an address from another Env
+fn moved(env: &Env, address: &Address) -> Address {
+    Address::try_from_val(env, &xdr::ScAddress::from(address)).unwrap()
+}
+
+#[test]
+fn test_round_with_thousands_of_tickets() {
+    // Snapshot capture is disabled: the ledger dump would be several megabytes
+    let env = Env::new_with_config(EnvTestConfig {
+        capture_snapshot_at_drop: false,
+    });
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig {
+        prize_tiers: vec![&env, 5_000u32, 3_000, 2_000],
+        ..round_config(&env, &token.address, 10, 24, true)
+    });
+    
+    // A handful of buyers share the tickets so the ledger holds few token balances
+    let ticket_count = 3000u64;
+    let mut buyers = Vec::new(&env);
+    for _ in 0..4 {
+        let buyer = Address::generate(&env);
+        token_admin_client.mint(&buyer, &(10 * ticket_count as i128));
+        buyers.push_back(buyer);
+    }
+    
+    // Tickets are bought in the largest batches allowed, and each buyer uses
+    // one seed for all their tickets, so ticket t belongs to buyer
+    // (t - 1) / batch_size % 4
+    let seed = [0u8; 4];
+    let batch_size = MAX_TICKETS_PER_CALL;
+    let batches = ticket_count / batch_size as u64;
+    let owner = |env: &Env, ticket_index: u64| moved(env, &buyers.get(((ticket_index - 1) / batch_size as u64 % 4) as u32).unwrap());
+    let mut env = env.clone();
+    let mut second_batch = None;
+    let mut last_batch = None;
+    for batch in 0..batches {
+        // A fresh Env every few batches keeps the test host's entry map small
+        if batch % 4 == 0 {
+            env = reloaded(&env, &contract_id);
+        }
+        let client = LotteryContractClient::new(&env, &moved(&env, &contract_id));
+        let participant = owner(&env, batch * batch_size as u64 + 1);
+        let commit = create_commit_hash(&env, &client.address, &Bytes::from_array(&env, &seed), &participant, round_id);
+        let mut commits = Vec::new(&env);
+        for _ in 0..batch_size {
+            commits.push_back(commit.clone());
+        }
+        client.buy_tickets(&round_id, &participant, &batch_size, &commits);
+        
+        // A buyer's first batch also opens their player history, so compare
+        // against the first repeat purchase
+        if batch == 4 {
+            second_batch = Some(env.cost_estimate().resources());
+        }
+        if batch == batches - 1 {
+            last_batch = Some(env.cost_estimate().resources());
+        }
+    }
+    
+    // Per-ticket cost must not grow with the number of tickets already sold
+    let second_batch = second_batch.unwrap();
+    let last_batch = last_batch.unwrap();
+    assert_eq!(last_batch.memory_read_entries, second_batch.memory_read_entries);
+    assert_eq!(last_batch.write_entries, second_batch.write_entries);
+    assert!(last_batch.write_bytes <= second_batch.write_bytes + 16 * batch_size);
+    
+    // A few tickets spread across the round are revealed, late ones first
+    let env = reloaded(&env, &contract_id);
+    let client = LotteryContractClient::new(&env, &moved(&env, &contract_id));
+    let round = client.view_round(&round_id);
+    assert_eq!(round.participants_count, ticket_count);
+    assert_eq!(round.total_pool, 10 * ticket_count as i128);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    let revealed = [2999u64, 2001, 1500, 1234, 42, 7];
+    for ticket_index in revealed {
+        client.reveal_seed(&round_id, &owner(&env, ticket_index), &ticket_index, &Bytes::from_array(&env, &seed));
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // Finalize reads the seed chain and the winners' entries rather than
+    // every ticket, so it fits the default budget with room to spare
+    let env = reloaded(&env, &contract_id);
+    let client = LotteryContractClient::new(&env, &moved(&env, &contract_id));
+    client.finalize_round(&moved(&env, &admin), &round_id);
+    let finalize = env.cost_estimate().resources();
+    assert!(finalize.instructions < 10_000_000, "finalize used {} instructions", finalize.instructions);
+    assert!(finalize.memory_read_entries < 40, "finalize read {} entries", finalize.memory_read_entries);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.winning_tickets.len(), 3);
+    for ticket_index in round.winning_tickets.iter() {
+        assert!(revealed.contains(&ticket_index));
+    }
+    assert!(client.verify_draw(&round_id));
+    let record = client.get_draw(&round_id).unwrap();
+    assert_eq!(draw_verifier::verify(&offchain_record(&record), &offchain_reveals(&client, round_id)), Ok(record.winning_tickets.iter().collect()));
+}
+
+#[test]
+fn test_cancel_round_without_participants() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    // Fast forward past end time with nobody buying
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    
+    // Anyone can close the empty round, no admin signature required
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    
+    let round = client.view_round(&round_id);
+    assert!(round.cancelled);
+    assert!(!round.is_active);
+    assert!(!round.finalized);
+    
+    // A new round can be created afterwards
+    env.mock_all_auths();
+    let next_round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(next_round_id, 2);
+}
+
+#[test]
+fn test_min_participants_refund() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { min_participants: 3, ..round_config(&env, &token.address, 100, 1, true) });
+    
+    // Alice buys two tickets, one short of the threshold; Bob buys none
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    client.buy_ticket(&round_id, &alice, &100, &create_commit_hash(&env, &contract_id, &seed, &alice, round_id));
+    client.buy_ticket(&round_id, &alice, &100, &create_commit_hash(&env, &contract_id, &seed, &alice, round_id));
+    
+    // Finalization is refused below the threshold
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 24 * 3600;
+    });
+    let result = client.try_finalize_round(&admin, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::NotEnoughParticipants)));
+    
+    client.cancel_round(&round_id);
+    let expected = RoundCancelled {
+        round_id,
+        participants_count: 2,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    
+    // Alice gets back both tickets
+    assert_eq!(client.claim_refund(&round_id, &alice), 200);
+    assert_eq!(token.balance(&alice), 1000);
+    assert_eq!(token.balance(&contract_id), 0);
+    
+    // Refunds can only be claimed once, and only by buyers
+    let result = client.try_claim_refund(&round_id, &alice);
+    assert_eq!(result, Err(Ok(LotteryError::RefundAlreadyClaimed)));
+    let result = client.try_claim_refund(&round_id, &bob);
+    assert_eq!(result, Err(Ok(LotteryError::NothingToRefund)));
+}
+
+#[test]
+fn test_admin_cancels_open_round() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    client.buy_ticket(&round_id, &participant, &100, &commit);
+    
+    // Refunds are not available while the round is running
+    let result = client.try_claim_refund(&round_id, &participant);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotCancelled)));
+    
+    // Sales are still open, so only the admin may cancel
+    client.cancel_round(&round_id);
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, admin);
+    
+    let result = client.try_buy_ticket(&round_id, &participant, &100, &commit);
+    assert_eq!(result, Err(Ok(LotteryError::RoundInactive)));
+    
+    let result = client.try_cancel_round(&round_id);
+    assert_eq!(result, Err(Ok(LotteryError::RoundInactive)));
+    
+    client.claim_refund(&round_id, &participant);
+    let expected = RefundClaimed {
+        round_id,
+        participant: participant.clone(),
+        amount: 100,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(token.balance(&participant), 1000);
+    
+    // Once sales close a round that can still be drawn cannot be cancelled
+    let drawable_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
+    client.buy_ticket(&drawable_round, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, drawable_round));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    let result = client.try_cancel_round(&drawable_round);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotCancellable)));
+    client.reveal_seed(&drawable_round, &participant, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    let result = client.try_cancel_round(&drawable_round);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotCancellable)));
+}
+
+// Helper function to run a one-ticket round through finalization, returning (round_id, winner)
+fn finalize_single_ticket_round(
+    env: &Env,
+    client: &LotteryContractClient,
+    admin: &Address,
+    token_admin_client: &token::StellarAssetClient,
+) -> (u64, Address) {
+    let round_id = client.create_round(admin, &round_config(env, &token_admin_client.address, 100, 1, false));
+    
+    let participant = Address::generate(env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(env, b"seed");
+    let commit = create_commit_hash(env, &client.address, &seed, &participant, round_id);
+    client.buy_ticket(&round_id, &participant, &100, &commit);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 1;
+    });
+    client.reveal_seed(&round_id, &participant, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    let winner = client.finalize_round(admin, &round_id).get(0).unwrap();
+    (round_id, winner)
+}
+
+#[test]
+fn test_claim_prize_rules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let (round_id, winner) = finalize_single_ticket_round(&env, &client, &admin, &token_admin_client);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.claim_deadline, env.ledger().timestamp() + 30 * 24 * 3600);
+    assert!(!round.prizes_claimed.get(0).unwrap());
+    
+    // Only the recorded winner can claim
+    let outsider = Address::generate(&env);
+    let result = client.try_claim_prize(&round_id, &outsider);
+    assert_eq!(result, Err(Ok(LotteryError::NotWinner)));
+    
+    // Nothing can be rolled over while the window is open
+    let result = client.try_expire_prize(&round_id);
+    assert_eq!(result, Err(Ok(LotteryError::ClaimWindowOpen)));
+    
+    client.claim_prize(&round_id, &winner);
+    assert!(client.view_round(&round_id).prizes_claimed.get(0).unwrap());
+    assert_eq!(token.balance(&winner), 1000);
+    
+    let result = client.try_claim_prize(&round_id, &winner);
+    assert_eq!(result, Err(Ok(LotteryError::PrizeAlreadyClaimed)));
+}
+
+#[test]
+fn test_claim_prize_before_finalize() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let result = client.try_claim_prize(&round_id, &admin);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotFinalized)));
+}
+
+#[test]
+fn test_unclaimed_prize_rolls_into_next_round() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let result = client.try_set_claim_window(&0);
+    assert_eq!(result, Err(Ok(LotteryError::InvalidClaimWindow)));
+    // A window that could push a claim deadline past the ledger clock is refused
+    let result = client.try_set_claim_window(&u64::MAX);
+    assert_eq!(result, Err(Ok(LotteryError::InvalidClaimWindow)));
+    let result = client.try_set_claim_window(&(MAX_CLAIM_WINDOW + 1));
+    assert_eq!(result, Err(Ok(LotteryError::InvalidClaimWindow)));
+    client.set_claim_window(&MAX_CLAIM_WINDOW);
+    client.set_claim_window(&3600);
+    assert_eq!(client.claim_window(), 3600);
+    
+    let (round_id, winner) = finalize_single_ticket_round(&env, &client, &admin, &token_admin_client);
+    
+    // Winner misses the one hour claim window
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    let result = client.try_claim_prize(&round_id, &winner);
+    assert_eq!(result, Err(Ok(LotteryError::ClaimExpired)));
+    
+    assert_eq!(client.expire_prize(&round_id), 100);
+    let expected = PrizeExpired {
+        round_id,
+        amount: 100,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    let result = client.try_expire_prize(&round_id);
+    assert_eq!(result, Err(Ok(LotteryError::AlreadyRolledOver)));
+    
+    // The next round starts with the expired prize in its pool
+    let next_round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    let next_round = client.view_round(&next_round_id);
+    assert_eq!(next_round.total_pool, 100);
+    assert_eq!(token.balance(&contract_id), 100);
+    
+    // Cancelling that round hands the carried prize on to the one after it
+    client.cancel_round(&next_round_id);
+    let third_round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(client.view_round(&third_round_id).total_pool, 100);
+}
+
+#[test]
+fn test_fee_configuration() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    assert_eq!(client.fee_bps(), 0);
+    assert_eq!(client.treasury(), None);
+    
+    // A fee needs somewhere to go
+    let result = client.try_set_fee(&admin, &500);
+    assert_eq!(result, Err(Ok(LotteryError::TreasuryNotConfigured)));
+    
+    let treasury = Address::generate(&env);
+    client.set_treasury(&admin, &treasury);
+    assert_eq!(client.treasury(), Some(treasury));
+    
+    // The fee is capped at 10%
+    let result = client.try_set_fee(&admin, &1_001);
+    assert_eq!(result, Err(Ok(LotteryError::InvalidFee)));
+    client.set_fee(&admin, &1_000);
+    
+    client.set_fee(&admin, &500);
+    assert_eq!(client.fee_bps(), 500);
+    
+    // The fee is fixed on the round when it is created
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    client.set_fee(&admin, &0);
+    assert_eq!(client.view_round(&round_id).fee_bps, 500);
+}
+
+#[test]
+fn test_fee_split_rounds_down() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let treasury = Address::generate(&env);
+    client.set_treasury(&admin, &treasury);
+    client.set_fee(&admin, &333); // 3.33%
+    
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut participants = Vec::new(&env);
+    for _ in 0..3 {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &100, &commit);
+        participants.push_back(participant);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for (i, participant) in participants.iter().enumerate() {
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // 300 * 333 / 10000 = 9.99, so the treasury takes 9 and the winner 291
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
+    let expected = FeeCollected {
+        round_id,
+        token: token.address.clone(),
+        amount: 9,
+    };
+    assert!(contract_events(&env, &contract_id).contains(as_published(&env, &contract_id, &expected)));
+    assert_eq!(token.balance(&treasury), 0);
+    assert_eq!(client.fees_accrued(&token.address), 9);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.fee_amount, 9);
+    assert_eq!(round.prize_amount, 291);
+    assert_eq!(round.prize_amount + round.fee_amount, round.total_pool);
+    
+    client.claim_prize(&round_id, &winner);
+    let expected = PrizePaid {
+        round_id,
+        winner: winner.clone(),
+        tiers: vec![&env, 0],
+        amount: 291,
+        fee_amount: 9,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(token.balance(&winner), 900 + 291);
+    assert_eq!(token.balance(&contract_id), 9);
+    
+    // The treasury pulls its fees when it chooses to
+    assert_eq!(client.claim_fees(&token.address), 9);
+    let auths = env.auths();
+    assert_eq!(auths.len(), 1);
+    assert_eq!(auths[0].0, treasury);
+    let expected = FeesClaimed {
+        treasury: treasury.clone(),
+        token: token.address.clone(),
+        amount: 9,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(token.balance(&treasury), 9);
+    assert_eq!(token.balance(&contract_id), 0);
+    assert_eq!(client.fees_accrued(&token.address), 0);
+    
+    assert_eq!(client.claim_fees(&token.address), 0);
+    assert_eq!(token.balance(&treasury), 9);
+}
+
+#[test]
+fn test_prize_paid_reports_each_tiers_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    client.set_treasury(&admin, &Address::generate(&env));
+    client.set_fee(&admin, &1_000); // 10%
+    
+    let round_id = client.create_round(&admin, &RoundConfig { prize_tiers: vec![&env, 6000u32, 4000], ..round_config(&env, &token.address, 100, 1, false) });
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut participants = Vec::new(&env);
+    for _ in 0..2 {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+        participants.push_back(participant);
+    }
+    
+    pass_time(&env, 3600);
+    for (i, participant) in participants.iter().enumerate() {
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
+    }
+    pass_time(&env, 24 * 3600);
+    let winners = client.finalize_round(&admin, &round_id);
+    
+    // The fee of 20 is split 60/40 like the prizes of 180, so each payout
+    // reports the fee its tier paid and the two add up to the round's fee
+    let round = client.view_round(&round_id);
+    assert_eq!(round.fee_amount, 20);
+    assert_eq!(round.prizes, vec![&env, 108i128, 72]);
+    for (tier, (winner, (amount, fee_amount))) in winners.iter().zip([(108i128, 12i128), (72, 8)]).enumerate() {
+        client.claim_prize(&round_id, &winner);
+        let expected = PrizePaid {
+            round_id,
+            winner: winner.clone(),
+            tiers: vec![&env, tier as u32],
+            amount,
+            fee_amount,
+        };
+        assert_eq!(
+            contract_events(&env, &contract_id),
+            vec![&env, as_published(&env, &contract_id, &expected)]
+        );
+    }
+}
+
+#[test]
+fn test_invalid_prize_tiers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let invalid_tiers = [
+        Vec::new(&env),
+        vec![&env, 5000u32, 3000],
+        vec![&env, 5000u32, 5000, 0],
+        vec![&env, 1000u32, 1000, 1000, 1000, 1000, 1000, 1000, 1000, 1000, 500, 500],
+    ];
+    for prize_tiers in invalid_tiers.iter() {
+        let result = client.try_create_round(&admin, &RoundConfig { prize_tiers: prize_tiers.clone(), ..round_config(&env, &token.address, 100, 1, false) });
+        assert_eq!(result, Err(Ok(LotteryError::InvalidPrizeTiers)));
+    }
+}
+
+#[test]
+fn test_multiple_prize_tiers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { prize_tiers: vec![&env, 5000u32, 3000, 2000], ..round_config(&env, &token.address, 100, 1, false) });
+    
+    let mut participants = Vec::new(&env);
+    let mut seeds = Vec::new(&env);
+    let seed_strings = [b"seed_0", b"seed_1", b"seed_2", b"seed_3", b"seed_4", b"seed_5", b"seed_6"];
+    for seed_string in seed_strings {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let seed = Bytes::from_slice(&env, seed_string);
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &100, &commit);
+        participants.push_back(participant);
+        seeds.push_back(seed);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for (i, participant) in participants.iter().enumerate() {
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seeds.get(i as u32).unwrap());
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    let winners = client.finalize_round(&admin, &round_id);
+    assert_eq!(winners.len(), 3);
+    
+    // The entropy inputs, then one event per tier, each for a different ticket
+    let events = contract_events(&env, &contract_id);
+    assert_eq!(events.len(), 4);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.winners, winners);
+    assert_eq!(round.winning_tickets.len(), 3);
+    for (i, ticket) in round.winning_tickets.iter().enumerate() {
+        assert!((1..=7).contains(&ticket));
+        assert_eq!(participants.get((ticket - 1) as u32).unwrap(), winners.get(i as u32).unwrap());
+        for other in round.winning_tickets.slice(i as u32 + 1..).iter() {
+            assert_ne!(ticket, other);
+        }
+    }
+    
+    // 700 split 50/30/20 percent
+    assert_eq!(round.prizes, vec![&env, 350i128, 210, 140]);
+    
+    for (tier, winner) in winners.iter().enumerate() {
+        let prize = round.prizes.get(tier as u32).unwrap();
+        assert_eq!(client.claim_prize(&round_id, &winner), prize);
+        assert_eq!(token.balance(&winner), 900 + prize);
+    }
+    assert_eq!(token.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_prize_tier_rounding_goes_to_first_tier() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { prize_tiers: vec![&env, 3334u32, 3333, 3333], ..round_config(&env, &token.address, 1, 1, true) });
+    
+    // One buyer holds every ticket, so they win all three tiers
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    for _ in 0..10 {
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &1, &commit);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for ticket_index in 1..=10 {
+        client.reveal_seed(&round_id, &participant, &ticket_index, &seed);
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    client.finalize_round(&admin, &round_id);
+    
+    // 10 * 33.34% = 3.334 and 10 * 33.33% = 3.333, dust of 1 goes to 1st place
+    let round = client.view_round(&round_id);
+    assert_eq!(round.prizes, vec![&env, 4i128, 3, 3]);
+    
+    // A single claim collects every tier the address won
+    assert_eq!(client.claim_prize(&round_id, &participant), 10);
+    let expected = PrizePaid {
+        round_id,
+        winner: participant.clone(),
+        tiers: vec![&env, 0, 1, 2],
+        amount: 10,
+        fee_amount: 0,
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(token.balance(&participant), 1000);
+}
+
+#[test]
+fn test_fewer_tickets_than_prize_tiers() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { prize_tiers: vec![&env, 5000u32, 3000, 2000], ..round_config(&env, &token.address, 100, 1, false) });
+    
+    for _ in 0..2 {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let seed = Bytes::from_slice(&env, b"seed");
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &100, &commit);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 24 * 3600;
+    });
+    
+    // Three tiers need three tickets, so the round can only be refunded
+    let result = client.try_finalize_round(&admin, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::NotEnoughParticipants)));
+    
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    assert!(client.view_round(&round_id).cancelled);
+}
+
+#[test]
+fn test_multiple_tickets_keep_separate_commits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, true));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    
+    let seed1 = Bytes::from_slice(&env, b"seed1");
+    let seed2 = Bytes::from_slice(&env, b"seed2");
+    client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed1, &participant, round_id));
+    client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed2, &participant, round_id));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 1;
+    });
+    
+    // The second purchase used to overwrite the first commit, so seed1 could never be revealed
+    let result = client.try_reveal_seed(&round_id, &participant, &1, &seed2);
+    assert_eq!(result, Err(Ok(LotteryError::CommitMismatch)));
+    client.reveal_seed(&round_id, &participant, &1, &seed1);
+    client.reveal_seed(&round_id, &participant, &2, &seed2);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    client.finalize_round(&admin, &round_id);
+    
+    // Both tickets' seeds went into the draw
+    let events = contract_events(&env, &contract_id);
+    let (_, _, data) = events.get(1).unwrap();
+    let data: Map<Symbol, Val> = data.into_val(&env);
+    let reveal_count: u64 = data.get(Symbol::new(&env, "reveal_count")).unwrap().into_val(&env);
+    assert_eq!(reveal_count, 2);
+}
+
+#[test]
+fn test_buy_tickets_batch() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, true));
+    
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    
+    let mut seeds = Vec::new(&env);
+    let mut commits = Vec::new(&env);
+    for seed_string in [b"alice_0", b"alice_1", b"alice_2"] {
+        let seed = Bytes::from_slice(&env, seed_string);
+        commits.push_back(create_commit_hash(&env, &contract_id, &seed, &alice, round_id));
+        seeds.push_back(seed);
+    }
+    
+    let result = client.try_buy_tickets(&round_id, &alice, &0, &Vec::new(&env));
+    assert_eq!(result, Err(Ok(LotteryError::InvalidTicketCount)));
+    let result = client.try_buy_tickets(&round_id, &alice, &2, &commits);
+    assert_eq!(result, Err(Ok(LotteryError::CommitCountMismatch)));
+    
+    // One payment covers all three tickets, each with its own slot
+    client.buy_tickets(&round_id, &alice, &3, &commits);
+    let events = contract_events(&env, &contract_id);
+    let mut expected = Vec::new(&env);
+    for ticket_index in 1..=3 {
+        let purchased = TicketPurchased {
+            round_id,
+            buyer: alice.clone(),
+            ticket_index,
+            amount: 100,
+        };
+        expected.push_back(as_published(&env, &contract_id, &purchased));
+    }
+    assert_eq!(events, expected);
+    assert_eq!(token.balance(&alice), 700);
+    
+    let bob_seed = Bytes::from_slice(&env, b"bob");
+    client.buy_tickets(&round_id, &bob, &1, &vec![&env, create_commit_hash(&env, &contract_id, &bob_seed, &bob, round_id)]);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.participants_count, 4);
+    assert_eq!(round.total_pool, 400);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600 + 1;
+    });
+    
+    // Tickets can only be revealed by their owner
+    let result = client.try_reveal_seed(&round_id, &bob, &1, &bob_seed);
+    assert_eq!(result, Err(Ok(LotteryError::NotTicketOwner)));
+    for i in 0..3 {
+        client.reveal_seed(&round_id, &alice, &(i as u64 + 1), &seeds.get(i).unwrap());
+    }
+    client.reveal_seed(&round_id, &bob, &4, &bob_seed);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // Alice owns tickets 1-3 and Bob ticket 4, so the winner follows the ticket drawn
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
+    let winning_ticket = client.view_round(&round_id).winning_tickets.get(0).unwrap();
+    if winning_ticket <= 3 {
+        assert_eq!(winner, alice);
+    } else {
+        assert_eq!(winner, bob);
+    }
+}
+
+#[test]
+fn test_buy_tickets_single_ticket_round() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    
+    // A batch cannot sneak extra tickets into a one-ticket-per-address round
+    let result = client.try_buy_tickets(&round_id, &participant, &2, &vec![&env, commit.clone(), commit.clone()]);
+    assert_eq!(result, Err(Ok(LotteryError::DuplicateTicket)));
+    
+    client.buy_tickets(&round_id, &participant, &1, &vec![&env, commit]);
+    assert_eq!(token.balance(&participant), 900);
+}
+
+#[test]
+fn test_largest_batch_fits_network_limits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig {
+        reveal_policy: RevealPolicy::Bond(50),
+        ..round_config(&env, &token.address, 100, 1, true)
+    });
+    
+    // A first-time buyer writes the most entries: their history and stats too
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &10_000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    let mut commits = Vec::new(&env);
+    for _ in 0..MAX_TICKETS_PER_CALL + 1 {
+        commits.push_back(commit.clone());
+    }
+    let result = client.try_buy_tickets(&round_id, &participant, &(MAX_TICKETS_PER_CALL + 1), &commits);
+    assert_eq!(result, Err(Ok(LotteryError::InvalidTicketCount)));
+    
+    commits.pop_back();
+    client.buy_tickets(&round_id, &participant, &MAX_TICKETS_PER_CALL, &commits);
+    
+    // Per-transaction limits of the Stellar network, protocol 23
+    let resources = env.cost_estimate().resources();
+    assert!(resources.instructions <= 100_000_000, "batch used {} instructions", resources.instructions);
+    assert!(resources.write_entries <= 50, "batch wrote {} entries", resources.write_entries);
+    let read_entries = resources.memory_read_entries + resources.disk_read_entries;
+    assert!(read_entries <= 100, "batch read {} entries", read_entries);
+    assert!(resources.write_bytes <= 132_096, "batch wrote {} bytes", resources.write_bytes);
+    assert!(resources.contract_events_size_bytes <= 16_384, "batch emitted {} event bytes", resources.contract_events_size_bytes);
+}
+
+#[test]
+fn test_invalid_reveal_policy() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    for bond in [0i128, -50] {
+        let result = client.try_create_round(&admin, &RoundConfig { reveal_policy: RevealPolicy::Bond(bond), ..round_config(&env, &token.address, 100, 1, false) });
+        assert_eq!(result, Err(Ok(LotteryError::InvalidRevealPolicy)));
+    }
+    
+    let round_id = client.create_round(&admin, &RoundConfig { reveal_policy: RevealPolicy::Bond(50), ..round_config(&env, &token.address, 100, 1, false) });
+    assert_eq!(client.view_round(&round_id).reveal_policy, RevealPolicy::Bond(50));
+}
+
+#[test]
+fn test_withheld_reveal_cannot_win() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let honest = Address::generate(&env);
+    let attacker = Address::generate(&env);
+    token_admin_client.mint(&honest, &10_000);
+    token_admin_client.mint(&attacker, &10_000);
+    let honest_seed = Bytes::from_slice(&env, b"honest");
+    let attacker_seeds = [Bytes::from_slice(&env, b"attacker_0"), Bytes::from_slice(&env, b"attacker_1")];
+    
+    // The attacker reveals last, sees every other seed and withholds one
+    // of their two tickets. Across several draws it never wins.
+    for _ in 0..5 {
+        let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, true));
+        client.buy_ticket(&round_id, &honest, &100, &create_commit_hash(&env, &contract_id, &honest_seed, &honest, round_id));
+        for seed in attacker_seeds.iter() {
+            client.buy_ticket(&round_id, &attacker, &100, &create_commit_hash(&env, &contract_id, seed, &attacker, round_id));
+        }
+        
+        env.ledger().with_mut(|li| {
+            li.timestamp += 3600;
+        });
+        client.reveal_seed(&round_id, &honest, &1, &honest_seed);
+        client.reveal_seed(&round_id, &attacker, &2, &attacker_seeds[0]);
+        env.ledger().with_mut(|li| {
+            li.timestamp += 24 * 3600;
+            li.sequence_number += 1;
+        });
+        
+        client.finalize_round(&admin, &round_id);
+        let round = client.view_round(&round_id);
+        assert_eq!(round.revealed_count, 2);
+        assert_ne!(round.winning_tickets.get(0).unwrap(), 3);
+        
+        let winner = round.winners.get(0).unwrap();
+        client.claim_prize(&round_id, &winner);
+    }
+}
+
+#[test]
+fn test_round_without_enough_reveals_is_refunded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { prize_tiers: vec![&env, 6000u32, 4000], ..round_config(&env, &token.address, 100, 1, false) });
+    
+    let mut participants = Vec::new(&env);
+    for seed_string in [b"seed_0", b"seed_1", b"seed_2"] {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let seed = Bytes::from_slice(&env, seed_string);
+        client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+        participants.push_back(participant);
+    }
+    
+    // Only one ticket is revealed, which cannot fill two prize tiers
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&round_id, &participants.get(0).unwrap(), &1, &Bytes::from_slice(&env, b"seed_0"));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    let result = client.try_finalize_round(&admin, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::NotEnoughReveals)));
+    
+    // Anyone may cancel a round left without enough eligible tickets
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    env.mock_all_auths();
+    
+    for participant in participants.iter() {
+        assert_eq!(client.claim_refund(&round_id, &participant), 100);
+        assert_eq!(token.balance(&participant), 1000);
+    }
+}
+
+#[test]
+fn test_reveal_bond_returned_or_forfeited() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig { reveal_policy: RevealPolicy::Bond(50), ..round_config(&env, &token.address, 100, 1, false) });
+    
+    let mut participants = Vec::new(&env);
+    let mut seeds = Vec::new(&env);
+    for seed_string in [b"seed_0", b"seed_1", b"seed_2"] {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let seed = Bytes::from_slice(&env, seed_string);
+        client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+        
+        // The bond is taken on top of the ticket price but kept out of the pool
+        assert_eq!(token.balance(&participant), 850);
+        participants.push_back(participant);
+        seeds.push_back(seed);
+    }
+    assert_eq!(client.view_round(&round_id).total_pool, 300);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for i in 0..2 {
+        let participant = participants.get(i).unwrap();
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seeds.get(i).unwrap());
+        assert_eq!(token.balance(&participant), 900);
+    }
+    
+    // Revealing twice must not hand the bond back twice
+    let result = client.try_reveal_seed(&round_id, &participants.get(0).unwrap(), &1, &seeds.get(0).unwrap());
+    assert_eq!(result, Err(Ok(LotteryError::AlreadyRevealed)));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // The withheld ticket's bond is added to the prize
+    let winner = client.finalize_round(&admin, &round_id).get(0).unwrap();
+    let round = client.view_round(&round_id);
+    assert_eq!(round.forfeited_bonds, 50);
+    assert_eq!(round.total_pool, 350);
+    assert_eq!(client.claim_prize(&round_id, &winner), 350);
+    assert_eq!(token.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_cancelled_round_refunds_outstanding_bonds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &RoundConfig {
+        min_participants: 4,
+        reveal_policy: RevealPolicy::Bond(50),
+        ..round_config(&env, &token.address, 100, 1, true)
+    });
+    
+    let revealer = Address::generate(&env);
+    let withholder = Address::generate(&env);
+    token_admin_client.mint(&revealer, &1000);
+    token_admin_client.mint(&withholder, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    for _ in 0..2 {
+        client.buy_ticket(&round_id, &revealer, &100, &create_commit_hash(&env, &contract_id, &seed, &revealer, round_id));
+    }
+    client.buy_ticket(&round_id, &withholder, &100, &create_commit_hash(&env, &contract_id, &seed, &withholder, round_id));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&round_id, &revealer, &1, &seed);
+    
+    // Three tickets fall short of the four required
+    client.cancel_round(&round_id);
+    
+    // One bond went back on reveal; the rest comes back with the ticket price
+    assert_eq!(client.claim_refund(&round_id, &revealer), 250);
+    assert_eq!(client.claim_refund(&round_id, &withholder), 150);
+    assert_eq!(token.balance(&revealer), 1000);
+    assert_eq!(token.balance(&withholder), 1000);
+    assert_eq!(token.balance(&contract_id), 0);
+}
+
+#[test]
+fn test_custom_sale_and_reveal_durations() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let start = env.ledger().timestamp();
+    let config = RoundConfig {
+        sale_duration: 5400,
+        reveal_duration: 7200,
+        ..round_config(&env, &token.address, 100, 1, false)
+    };
+    let round_id = client.create_round(&admin, &config);
+    
+    let round = client.view_round(&round_id);
+    assert_eq!(round.end_time, start + 5400);
+    assert_eq!(round.reveal_deadline, start + 5400 + 7200);
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+    
+    // The reveal window closes after two hours rather than a full day
+    env.ledger().with_mut(|li| {
+        li.timestamp = start + 5400 + 7200;
+    });
+    let result = client.try_reveal_seed(&round_id, &participant, &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::RevealWindowClosed)));
+}
+
+#[test]
+fn test_duration_bounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let bounds = DurationBounds {
+        min_sale_duration: 600,
+        max_sale_duration: 3600,
+        min_reveal_duration: 300,
+        max_reveal_duration: 1800,
+    };
+    
+    let invalid_bounds = [
+        DurationBounds { min_sale_duration: 0, ..bounds.clone() },
+        DurationBounds { min_sale_duration: 7200, ..bounds.clone() },
+        DurationBounds { min_reveal_duration: 0, ..bounds.clone() },
+        DurationBounds { max_reveal_duration: 299, ..bounds.clone() },
+        // Together a sale and its reveal window must fit in 28 days
+        DurationBounds { max_sale_duration: 21 * 24 * 3600, max_reveal_duration: 7 * 24 * 3600 + 1, ..bounds.clone() },
+        DurationBounds { max_sale_duration: u64::MAX, max_reveal_duration: u64::MAX, ..bounds.clone() },
+    ];
+    for invalid in invalid_bounds.iter() {
+        let result = client.try_set_duration_bounds(invalid);
+        assert_eq!(result, Err(Ok(LotteryError::InvalidDurationBounds)));
+    }
+    
+    client.set_duration_bounds(&bounds);
+    assert_eq!(client.duration_bounds(), bounds);
+    
+    // Durations outside the bounds are rejected, the bounds themselves are allowed
+    let base = RoundConfig {
+        sale_duration: 600,
+        reveal_duration: 300,
+        ..round_config(&env, &token.address, 100, 1, false)
+    };
+    let out_of_bounds = [
+        RoundConfig { sale_duration: 599, ..base.clone() },
+        RoundConfig { sale_duration: 3601, ..base.clone() },
+        RoundConfig { reveal_duration: 299, ..base.clone() },
+        RoundConfig { reveal_duration: 1801, ..base.clone() },
+    ];
+    for config in out_of_bounds.iter() {
+        let result = client.try_create_round(&admin, config);
+        assert_eq!(result, Err(Ok(LotteryError::InvalidDuration)));
+    }
+    
+    client.create_round(&admin, &RoundConfig { sale_duration: 3600, reveal_duration: 1800, ..base.clone() });
+    
+    // Deadlines past the end of the ledger clock are rejected, not wrapped:
+    // first the reveal deadline, then the end of sales too
+    for timestamp in [u64::MAX - 700, u64::MAX - 100] {
+        env.ledger().with_mut(|li| {
+            li.timestamp = timestamp;
+        });
+        let result = client.try_create_round(&admin, &base);
+        assert_eq!(result, Err(Ok(LotteryError::InvalidDuration)));
+    }
+}
+
+// Helper function to let time pass on the ledger, one ledger every five seconds
+fn pass_time(env: &Env, seconds: u64) {
+    env.ledger().with_mut(|li| {
+        li.timestamp += seconds;
+        li.sequence_number += (seconds / 5) as u32;
+    });
+}
+
+#[test]
+fn test_round_entries_outlive_its_deadlines() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // The longest round the default bounds allow
+    let bounds = client.duration_bounds();
+    let round_id = client.create_round(&admin, &RoundConfig {
+        sale_duration: bounds.max_sale_duration,
+        reveal_duration: bounds.max_reveal_duration,
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+    
+    // Tickets bought on the first day are still live at the draw
+    pass_time(&env, bounds.max_sale_duration);
+    client.reveal_seed(&round_id, &participant, &1, &seed);
+    pass_time(&env, bounds.max_reveal_duration);
+    client.finalize_round(&admin, &round_id);
+    
+    // Finalizing bumps the entries verify_draw reads, so the draw stays
+    // checkable through the claim window without restoring anything
+    let claim_window_ledgers = (client.claim_window() / 5) as u32;
+    env.as_contract(&contract_id, || {
+        let storage = env.storage().persistent();
+        assert!(storage.get_ttl(&RoundBook::Round(round_id)) >= claim_window_ledgers - DAY_IN_LEDGERS);
+        assert!(storage.get_ttl(&RevealBook::Step(round_id, 1)) >= claim_window_ledgers - DAY_IN_LEDGERS);
+        assert!(storage.get_ttl(&ParticipantBook::Participant(round_id, 1)) >= claim_window_ledgers - DAY_IN_LEDGERS);
+    });
+    pass_time(&env, client.claim_window() - 3600);
+    assert!(client.verify_draw(&round_id));
+    assert_eq!(client.claim_prize(&round_id, &participant), 100);
+}
+
+#[test]
+fn test_concurrent_rounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // A daily round and a weekly jackpot run at the same time
+    let daily_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 24, false));
+    let weekly_id = client.create_round(&admin, &round_config(&env, &token.address, 500, 7 * 24, false));
+    assert_eq!(client.active_rounds(), vec![&env, daily_id, weekly_id]);
+    
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    let alice_seed = Bytes::from_slice(&env, b"alice");
+    let bob_seed = Bytes::from_slice(&env, b"bob");
+    
+    client.buy_ticket(&daily_id, &alice, &100, &create_commit_hash(&env, &contract_id, &alice_seed, &alice, daily_id));
+    client.buy_ticket(&weekly_id, &alice, &500, &create_commit_hash(&env, &contract_id, &alice_seed, &alice, weekly_id));
+    client.buy_ticket(&weekly_id, &bob, &500, &create_commit_hash(&env, &contract_id, &bob_seed, &bob, weekly_id));
+    
+    // Each round keeps its own pool and ticket numbering
+    let daily = client.view_round(&daily_id);
+    let weekly = client.view_round(&weekly_id);
+    assert_eq!((daily.total_pool, daily.participants_count), (100, 1));
+    assert_eq!((weekly.total_pool, weekly.participants_count), (1000, 2));
+    assert_eq!(client.open_rounds().len(), 2);
+    
+    // The daily round closes and is drawn while the weekly one keeps selling
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    let open_rounds = client.open_rounds();
+    assert_eq!(open_rounds.len(), 1);
+    assert_eq!(open_rounds.get(0).unwrap().round_id, weekly_id);
+    client.reveal_seed(&daily_id, &alice, &1, &alice_seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    assert_eq!(client.finalize_round(&admin, &daily_id), vec![&env, alice.clone()]);
+    assert_eq!(client.active_rounds(), vec![&env, weekly_id]);
+    assert_eq!(client.claim_prize(&daily_id, &alice), 100);
+    
+    let charlie = Address::generate(&env);
+    token_admin_client.mint(&charlie, &1000);
+    let charlie_seed = Bytes::from_slice(&env, b"charlie");
+    client.buy_ticket(&weekly_id, &charlie, &500, &create_commit_hash(&env, &contract_id, &charlie_seed, &charlie, weekly_id));
+    
+    // The weekly pool was untouched by the daily payout
+    let weekly = client.view_round(&weekly_id);
+    assert_eq!((weekly.total_pool, weekly.participants_count), (1500, 3));
+    assert_eq!(token.balance(&contract_id), 1500);
+}
+
+#[test]
+fn test_token_allowlist() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    let (usdc, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    assert_eq!(client.allowed_tokens(), vec![&env, token.address.clone()]);
+    
+    // Rounds can only be priced in allowlisted tokens
+    let result = client.try_create_round(&admin, &round_config(&env, &usdc.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::TokenNotConfigured)));
+    
+    client.allow_token(&usdc.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &usdc.address, 100, 1, false));
+    assert_eq!(client.view_round(&round_id).token, usdc.address);
+    
+    // Removing a token stops new rounds but leaves running ones alone
+    client.disallow_token(&usdc.address);
+    assert_eq!(client.allowed_tokens(), vec![&env, token.address.clone()]);
+    let result = client.try_create_round(&admin, &round_config(&env, &usdc.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::TokenNotConfigured)));
+    assert!(client.view_round(&round_id).is_active);
+    
+    let unknown = Address::generate(&env);
+    let result = client.try_disallow_token(&unknown);
+    assert_eq!(result, Err(Ok(LotteryError::TokenNotConfigured)));
+}
+
+#[test]
+fn test_rounds_in_different_tokens() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (xlm, xlm_admin_client) = create_token_contract(&env, &token_admin);
+    let (usdc, usdc_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &xlm.address);
+    client.allow_token(&usdc.address);
+    
+    let xlm_round = client.create_round(&admin, &round_config(&env, &xlm.address, 100, 1, false));
+    let usdc_round = client.create_round(&admin, &round_config(&env, &usdc.address, 30, 1, false));
+    
+    let participant = Address::generate(&env);
+    xlm_admin_client.mint(&participant, &1000);
+    usdc_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    
+    // Each ticket is paid in its round's token
+    client.buy_ticket(&xlm_round, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, xlm_round));
+    client.buy_ticket(&usdc_round, &participant, &30, &create_commit_hash(&env, &contract_id, &seed, &participant, usdc_round));
+    assert_eq!(xlm.balance(&participant), 900);
+    assert_eq!(usdc.balance(&participant), 970);
+    
+    let mut expected = Map::new(&env);
+    expected.set(xlm.address.clone(), 100i128);
+    expected.set(usdc.address.clone(), 30i128);
+    assert_eq!(client.token_balances(&0, &10), expected);
+    
+    // Balances page through the allowlist like the other listings
+    let first = client.token_balances(&0, &1);
+    let second = client.token_balances(&1, &1);
+    assert_eq!(first.len(), 1);
+    assert_eq!(second.len(), 1);
+    assert_ne!(first.keys(), second.keys());
+    assert_eq!(client.token_balances(&2, &10).len(), 0);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&usdc_round, &participant, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // Prizes go out in the round's token, refunds too
+    client.finalize_round(&admin, &usdc_round);
+    assert_eq!(client.claim_prize(&usdc_round, &participant), 30);
+    assert_eq!(usdc.balance(&participant), 1000);
+    
+    client.cancel_round(&xlm_round);
+    assert_eq!(client.claim_refund(&xlm_round, &participant), 100);
+    assert_eq!(xlm.balance(&participant), 1000);
+    
+    let mut expected = Map::new(&env);
+    expected.set(xlm.address.clone(), 0i128);
+    expected.set(usdc.address.clone(), 0i128);
+    assert_eq!(client.token_balances(&0, &10), expected);
+}
+
+#[test]
+fn test_upgrade_from_v1_build() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    // Start from the schema version 1 build
+    let contract_id = env.register(lottery_v1::WASM, ());
+    let v1_client = lottery_v1::Client::new(&env, &contract_id);
+    
+    v1_client.init_admin(&admin, &token.address);
+    let round_id = v1_client.create_round(&lottery_v1::RoundConfig {
+        token: token.address.clone(),
+        ticket_price: 100,
+        sale_duration: 3600,
+        reveal_duration: 24 * 3600,
+        allow_multiple: false,
+        min_participants: 0,
+        prize_tiers: vec![&env, 10_000u32],
+        reveal_policy: lottery_v1::RevealPolicy::Exclude,
+    });
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    v1_client.buy_ticket(&round_id, &participant, &100, &create_v1_commit_hash(&env, &seed, &participant, round_id));
+    
+    // Upgrade to the build under test. Native test contracts run from the
+    // empty WASM, and register_at attaches this crate's code to it.
+    let native_hash = env.deployer().upload_contract_wasm(Bytes::new(&env));
+    v1_client.upgrade(&native_hash);
+    env.register_at(&contract_id, LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    client.set_keeper_reward(&200);
+    
+    // Round history and configuration survive the upgrade, and the version 1
+    // round is readable even before it has been migrated, taking the keeper
+    // reward in force until then
+    assert_eq!(client.schema_version(), 1);
+    assert_eq!(client.active_rounds(), vec![&env, round_id]);
+    let round = client.get_round(&round_id).unwrap();
+    assert_eq!(round.participants_count, 1);
+    assert_eq!(round.total_pool, 100);
+    assert_eq!(round.entropy_mode, EntropyMode::CommitReveal);
+    assert_eq!(round.commit_scheme, CommitScheme::V1);
+    assert_eq!(round.keeper_bps, 200);
+    
+    // The version 1 build kept no owner index, so its tickets are reported
+    // as unindexed rather than left out, and get_participants still has them
+    assert_eq!(client.try_tickets_of(&round_id, &participant, &0, &10), Err(Ok(LotteryError::TicketsNotIndexed)));
+    assert_eq!(client.try_get_commit(&round_id, &participant), Err(Ok(LotteryError::TicketsNotIndexed)));
+    assert_eq!(client.tickets_of(&round_id, &participant, &1, &10), Vec::new(&env));
+    assert_eq!(client.get_participants(&round_id, &0, &10), vec![&env, participant.clone()]);
+    assert_eq!(client.get_ticket(&round_id, &1).unwrap().owner, participant);
+    
+    // A round created mid-migration is already in the new layout
+    let new_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    // Migrating rewrites both rounds, in batches, then bumps the version
+    assert_eq!(client.migrate(&1), 1);
+    assert_eq!(client.schema_version(), 1);
+    assert_eq!(client.migrate(&10), 1);
+    assert_eq!(client.schema_version(), 2);
+    assert_eq!(client.migrate(&10), 0);
+    let stored: LotteryRound = env.as_contract(&contract_id, || {
+        env.storage().persistent().get(&RoundBook::Round(round_id)).unwrap()
+    });
+    assert_eq!(stored.entropy_mode, EntropyMode::CommitReveal);
+    assert_eq!(stored.commit_scheme, CommitScheme::V1);
+    assert_eq!(stored.keeper_bps, 200);
+    client.set_keeper_reward(&0);
+    assert_eq!(client.get_round(&round_id).unwrap().keeper_bps, 200);
+    assert_eq!(client.get_round(&new_round).unwrap().ticket_price, 100);
+    assert_eq!(client.get_round(&new_round).unwrap().commit_scheme, CommitScheme::V2);
+    
+    // The round carries on under the new build
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&round_id, &participant, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    assert_eq!(client.finalize_round(&admin, &round_id), vec![&env, participant.clone()]);
+    assert_eq!(token.balance(&admin), 2);
+    assert_eq!(client.claim_prize(&round_id, &participant), 98);
+    assert_eq!(token.balance(&participant), 998);
+}
+
+#[test]
+fn test_upgrade_keeps_v1_commits_for_rounds_in_progress() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    // Start from the schema version 1 build, with a round on sale
+    let contract_id = env.register(lottery_v1::WASM, ());
+    let v1_client = lottery_v1::Client::new(&env, &contract_id);
+    
+    v1_client.init_admin(&admin, &token.address);
+    let old_round = v1_client.create_round(&lottery_v1::RoundConfig {
+        token: token.address.clone(),
+        ticket_price: 100,
+        sale_duration: 3600,
+        reveal_duration: 24 * 3600,
+        allow_multiple: false,
+        min_participants: 0,
+        prize_tiers: vec![&env, 10_000u32],
+        reveal_policy: lottery_v1::RevealPolicy::Exclude,
+    });
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let early = Address::generate(&env);
+    token_admin_client.mint(&early, &1000);
+    v1_client.buy_ticket(&old_round, &early, &100, &create_v1_commit_hash(&env, &seed, &early, old_round));
+    
+    let native_hash = env.deployer().upload_contract_wasm(Bytes::new(&env));
+    v1_client.upgrade(&native_hash);
+    env.register_at(&contract_id, LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    // The round in progress keeps the scheme its first buyers committed
+    // under, including for tickets sold after the upgrade
+    assert_eq!(client.schema_version(), 1);
+    assert_eq!(client.get_round(&old_round).unwrap().commit_scheme, CommitScheme::V1);
+    let late = Address::generate(&env);
+    token_admin_client.mint(&late, &1000);
+    client.buy_ticket(&old_round, &late, &100, &create_commit_hash(&env, &contract_id, &seed, &late, old_round));
+    
+    // New rounds use V2 straight away
+    let new_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(client.get_round(&new_round).unwrap().commit_scheme, CommitScheme::V2);
+    client.buy_ticket(&new_round, &early, &100, &create_commit_hash(&env, &contract_id, &seed, &early, new_round));
+    client.buy_ticket(&new_round, &late, &100, &create_v1_commit_hash(&env, &seed, &late, new_round));
+    
+    assert_eq!(client.migrate(&10), 2);
+    assert_eq!(client.schema_version(), 2);
+    let stored: LotteryRound = env.as_contract(&contract_id, || {
+        env.storage().persistent().get(&RoundBook::Round(old_round)).unwrap()
+    });
+    assert_eq!(stored.commit_scheme, CommitScheme::V1);
+    
+    // Each round only accepts reveals under its own scheme
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&old_round, &early, &1, &seed);
+    assert_eq!(client.try_reveal_seed(&old_round, &late, &2, &seed), Err(Ok(LotteryError::CommitMismatch)));
+    client.reveal_seed(&new_round, &early, &1, &seed);
+    assert_eq!(client.try_reveal_seed(&new_round, &late, &2, &seed), Err(Ok(LotteryError::CommitMismatch)));
+}
+
+#[test]
+fn test_upgrade_cancels_rounds_revealed_without_seed_chain() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    // Start from the schema version 1 build and reveal one of two tickets
+    // there, before the round has a seed chain
+    let contract_id = env.register(lottery_v1::WASM, ());
+    let v1_client = lottery_v1::Client::new(&env, &contract_id);
+    
+    v1_client.init_admin(&admin, &token.address);
+    let round_id = v1_client.create_round(&lottery_v1::RoundConfig {
+        token: token.address.clone(),
+        ticket_price: 100,
+        sale_duration: 3600,
+        reveal_duration: 24 * 3600,
+        allow_multiple: false,
+        min_participants: 0,
+        prize_tiers: vec![&env, 10_000u32],
+        reveal_policy: lottery_v1::RevealPolicy::Exclude,
+    });
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    for participant in [&first, &second] {
+        token_admin_client.mint(participant, &1000);
+        v1_client.buy_ticket(&round_id, participant, &100, &create_v1_commit_hash(&env, &seed, participant, round_id));
+    }
+    pass_time(&env, 3600);
+    v1_client.reveal_seed(&round_id, &first, &1, &seed);
+    
+    let native_hash = env.deployer().upload_contract_wasm(Bytes::new(&env));
+    v1_client.upgrade(&native_hash);
+    env.register_at(&contract_id, LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    // Reveals still go through, but the round has no chain to draw from
+    client.reveal_seed(&round_id, &second, &2, &seed);
+    assert_eq!(client.get_round(&round_id).unwrap().revealed_count, 2);
+    assert_eq!(client.get_reveals(&round_id, &0, &10), Vec::new(&env));
+    assert_eq!(client.try_cancel_round(&round_id), Err(Ok(LotteryError::RoundNotCancellable)));
+    
+    // So once the reveal window closes it is cancelled rather than drawn,
+    // without waiting for the finalize grace period
+    pass_time(&env, 24 * 3600);
+    assert_eq!(client.try_finalize_round(&admin, &round_id), Err(Ok(LotteryError::NotEnoughReveals)));
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    env.mock_all_auths();
+    assert!(client.get_round(&round_id).unwrap().cancelled);
+    assert_eq!(client.claim_refund(&round_id, &first), 100);
+    assert_eq!(client.claim_refund(&round_id, &second), 100);
+    assert_eq!(token.balance(&first), 1000);
+}
+
+#[test]
+fn test_upgrade_requires_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let v1_hash = env.deployer().upload_contract_wasm(lottery_v1::WASM);
+    
+    env.set_auths(&[]);
+    assert!(client.try_upgrade(&v1_hash).is_err());
+    assert!(client.try_migrate(&10).is_err());
+    
+    // The admin can move the contract onto another build, keeping its storage
+    env.mock_all_auths();
+    client.upgrade(&v1_hash);
+    let v1_client = lottery_v1::Client::new(&env, &contract_id);
+    assert_eq!(v1_client.allowed_tokens(), vec![&env, token.address.clone()]);
+    // It reports the layout of what is in storage
+    assert_eq!(v1_client.schema_version(), 2);
+}
+
+#[test]
+fn test_two_step_admin_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let new_admin = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    
+    // Accepting without a proposal fails
+    let result = client.try_accept_admin(&new_admin);
+    assert_eq!(result, Err(Ok(LotteryError::NoPendingAdmin)));
+    
+    client.propose_admin(&new_admin);
+    assert_eq!(client.pending_admin(), Some(new_admin.clone()));
+    assert_eq!(client.admin(), Some(admin.clone()));
+    
+    // Only the proposed address can take over
+    let result = client.try_accept_admin(&stranger);
+    assert_eq!(result, Err(Ok(LotteryError::NoPendingAdmin)));
+    
+    client.accept_admin(&new_admin);
+    let expected = AdminChanged {
+        previous_admin: admin.clone(),
+        new_admin: new_admin.clone(),
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(client.admin(), Some(new_admin.clone()));
+    assert_eq!(client.pending_admin(), None);
+    
+    // The old admin lost its rights, the new one has them
+    let result = client.try_create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    client.create_round(&new_admin, &round_config(&env, &token.address, 100, 1, false));
+}
+
+#[test]
+fn test_roles() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // An ops bot that runs rounds without the admin key
+    let bot = Address::generate(&env);
+    let result = client.try_create_round(&bot, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    
+    client.grant_role(&bot, &Role::RoundOperator);
+    let expected = RoleGranted {
+        role: Role::RoundOperator,
+        account: bot.clone(),
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    client.grant_role(&bot, &Role::Finalizer);
+    assert!(client.has_role(&bot, &Role::RoundOperator));
+    assert!(!client.has_role(&bot, &Role::FeeManager));
+    
+    let round_id = client.create_round(&bot, &round_config(&env, &token.address, 100, 1, false));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&round_id, &participant, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    client.finalize_round(&bot, &round_id);
+    
+    // Roles are separate: the bot cannot touch fees
+    let result = client.try_set_fee(&bot, &100);
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    let fee_manager = Address::generate(&env);
+    client.grant_role(&fee_manager, &Role::FeeManager);
+    client.set_treasury(&fee_manager, &Address::generate(&env));
+    client.set_fee(&fee_manager, &100);
+    assert_eq!(client.fee_bps(), 100);
+    
+    client.revoke_role(&bot, &Role::RoundOperator);
+    assert!(!client.has_role(&bot, &Role::RoundOperator));
+    let result = client.try_create_round(&bot, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    
+    // Only the admin grants roles
+    env.set_auths(&[]);
+    assert!(client.try_grant_role(&bot, &Role::RoundOperator).is_err());
+}
+
+#[test]
+fn test_finalize_pays_keeper() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let treasury = Address::generate(&env);
+    client.set_treasury(&admin, &treasury);
+    client.set_fee(&admin, &500); // 5%
+    
+    let result = client.try_set_keeper_reward(&501);
+    assert_eq!(result, Err(Ok(LotteryError::InvalidKeeperReward)));
+    client.set_keeper_reward(&100); // 1%
+    assert_eq!(client.keeper_reward(), 100);
+    
+    // The reward is fixed when the round is created
+    let mut config = round_config(&env, &token.address, 1000, 1, false);
+    config.entropy_mode = EntropyMode::CommitRevealPrng;
+    let round_id = client.create_round(&admin, &config);
+    client.set_keeper_reward(&500);
+    assert_eq!(client.get_round(&round_id).unwrap().keeper_bps, 100);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut participants = Vec::new(&env);
+    for _ in 0..2 {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+        client.buy_ticket(&round_id, &participant, &1000, &commit);
+        participants.push_back(participant);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for (i, participant) in participants.iter().enumerate() {
+        client.reveal_seed(&round_id, &participant, &(i as u64 + 1), &seed);
+    }
+    
+    // The PRNG is drawn while finalizing, so the keeper needs the Finalizer
+    // role, and still has to wait for the reveal deadline
+    let keeper = Address::generate(&env);
+    assert_eq!(client.try_finalize_round(&keeper, &round_id), Err(Ok(LotteryError::NotAuthorized)));
+    client.grant_role(&keeper, &Role::Finalizer);
+    let result = client.try_finalize_round(&keeper, &round_id);
+    assert_eq!(result, Err(Ok(LotteryError::RevealDeadlineNotPassed)));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // 2000 * 1% = 20 to the keeper; 1980 * 5% = 99 to the treasury
+    let winner = client.finalize_round(&keeper, &round_id).get(0).unwrap();
+    let expected = KeeperRewarded {
+        round_id,
+        keeper: keeper.clone(),
+        amount: 20,
+    };
+    let events = contract_events(&env, &contract_id);
+    assert_eq!(
+        events.slice(events.len() - 1..),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(token.balance(&keeper), 20);
+    assert_eq!(client.fees_accrued(&token.address), 99);
+    
+    let round = client.view_round(&round_id);
+    assert!(round.finalized);
+    assert_eq!(round.prize_amount, 1881);
+    assert_eq!(round.prize_amount + round.fee_amount + 20, round.total_pool);
+    
+    client.claim_prize(&round_id, &winner);
+    client.claim_fees(&token.address);
+    assert_eq!(token.balance(&treasury), 99);
+    assert_eq!(token.balance(&contract_id), 0);
+    
+    // Only the admin sets the reward
+    env.set_auths(&[]);
+    assert!(client.try_set_keeper_reward(&0).is_err());
+}
+
+#[test]
+fn test_permissionless_finalize_pays_keeper() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    let provider_id = env.register(MockRandomnessProvider, ());
+    let provider = MockRandomnessProviderClient::new(&env, &provider_id);
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    
+    client.init_admin(&admin, &token.address);
+    client.set_keeper_reward(&100); // 1%
+    client.set_randomness_provider(&Some(RandomnessProvider {
+        contract: provider_id.clone(),
+        public_key: BytesN::from_array(&env, &key.verifying_key().to_bytes()),
+    }));
+    let config = RoundConfig { entropy_mode: EntropyMode::Provider, ..round_config(&env, &token.address, 1000, 1, false) };
+    let round_id = client.create_round(&admin, &config);
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    for _ in 0..2 {
+        let participant = Address::generate(&env);
+        token_admin_client.mint(&participant, &1000);
+        client.buy_ticket(&round_id, &participant, &1000, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+    }
+    pass_time(&env, 3600 + 24 * 3600);
+    
+    // Provider rounds are drawn in the provider's transaction rather than
+    // the caller's, so a keeper holding no role may finalize one, and is
+    // paid when the provider's answer draws it: 2000 * 1% = 20
+    let keeper = Address::generate(&env);
+    assert!(!client.has_role(&keeper, &Role::Finalizer));
+    assert_eq!(client.finalize_round(&keeper, &round_id), Vec::new(&env));
+    let request = client.randomness_request(&round_id).unwrap();
+    provider.fulfill(&round_id, &sign_request(&env, &key, &request.request_id));
+    let expected = KeeperRewarded {
+        round_id,
+        keeper: keeper.clone(),
+        amount: 20,
+    };
+    assert!(contract_events(&env, &contract_id).contains(as_published(&env, &contract_id, &expected)));
+    assert_eq!(token.balance(&keeper), 20);
+    assert_eq!(client.get_round(&round_id).unwrap().prize_amount, 1980);
+}
+
+#[test]
+fn test_commit_reveal_rounds_need_a_finalizer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let mut round_ids = Vec::new(&env);
+    for _ in 0..2 {
+        let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+        client.buy_ticket(&round_id, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, round_id));
+        round_ids.push_back(round_id);
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for round_id in round_ids.iter() {
+        client.reveal_seed(&round_id, &participant, &1, &seed);
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // Every seed is public now and only the ledger sequence is left to mix
+    // in, so a caller without the role could pick a ledger that suits them
+    let keeper = Address::generate(&env);
+    let result = client.try_finalize_round(&keeper, &round_ids.get(0).unwrap());
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    
+    let finalizer = Address::generate(&env);
+    client.grant_role(&finalizer, &Role::Finalizer);
+    assert_eq!(client.finalize_round(&finalizer, &round_ids.get(0).unwrap()), vec![&env, participant.clone()]);
+    
+    // A round no finalizer draws can be cancelled by anyone once the grace
+    // period runs out, so the tickets are refunded rather than locked
+    let round_id = round_ids.get(1).unwrap();
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600 - 1;
+    });
+    assert_eq!(client.try_cancel_round(&round_id), Err(Ok(LotteryError::RoundNotCancellable)));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 1;
+    });
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    env.mock_all_auths();
+    assert_eq!(client.claim_refund(&round_id, &participant), 100);
+    assert_eq!(client.try_finalize_round(&admin, &round_id), Err(Ok(LotteryError::RoundInactive)));
+}
+
+#[test]
+fn test_in_transaction_draws_need_a_finalizer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    let attacker_id = env.register(WinOrRevert, ());
+    let attacker = WinOrRevertClient::new(&env, &attacker_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // The attacker holds one ticket of four in a round of each mode that
+    // draws inside the finalizing call
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut round_ids = Vec::new(&env);
+    for mode in [EntropyMode::CommitRevealPrng, EntropyMode::Prng] {
+        let config = RoundConfig { entropy_mode: mode, ..round_config(&env, &token.address, 100, 1, false) };
+        let round_id = client.create_round(&admin, &config);
+        for buyer in [attacker_id.clone(), Address::generate(&env), Address::generate(&env), Address::generate(&env)] {
+            token_admin_client.mint(&buyer, &100);
+            client.buy_ticket(&round_id, &buyer, &100, &create_commit_hash(&env, &contract_id, &seed, &buyer, round_id));
+        }
+        round_ids.push_back(round_id);
+    }
+    pass_time(&env, 3600);
+    let round_id = round_ids.get(0).unwrap();
+    for ticket_index in 1..=4u64 {
+        let owner = client.get_ticket(&round_id, &ticket_index).unwrap().owner;
+        client.reveal_seed(&round_id, &owner, &ticket_index, &seed);
+    }
+    pass_time(&env, 24 * 3600);
+    
+    // Without the role the attacker never gets to see a draw, on any ledger
+    for _ in 0..10 {
+        for round_id in round_ids.iter() {
+            assert!(attacker.try_attack(&contract_id, &round_id).is_err());
+            assert_eq!(client.try_finalize_round(&attacker_id, &round_id), Err(Ok(LotteryError::NotAuthorized)));
+        }
+        pass_time(&env, 5);
+    }
+    
+    // That is what the role guards against: a Finalizer contract could keep
+    // reverting its losing draws, each leaving the round as it was, until
+    // one went its way
+    client.grant_role(&attacker_id, &Role::Finalizer);
+    let round_id = round_ids.get(1).unwrap();
+    let mut attempts = 0;
+    while attacker.try_attack(&contract_id, &round_id).is_err() {
+        assert!(!client.get_round(&round_id).unwrap().finalized);
+        attempts += 1;
+        assert!(attempts < 50);
+        pass_time(&env, 5);
+    }
+    assert_eq!(client.get_round(&round_id).unwrap().winners, vec![&env, attacker_id.clone()]);
+    client.revoke_role(&attacker_id, &Role::Finalizer);
+    
+    // A round no finalizer draws is cancelled by anyone once the grace
+    // period runs out, as for CommitReveal rounds
+    let round_id = round_ids.get(0).unwrap();
+    assert_eq!(client.try_cancel_round(&round_id), Err(Ok(LotteryError::RoundNotCancellable)));
+    pass_time(&env, 24 * 3600);
+    env.set_auths(&[]);
+    client.cancel_round(&round_id);
+    env.mock_all_auths();
+    assert_eq!(client.claim_refund(&round_id, &attacker_id), 100);
+    assert_eq!(token.balance(&attacker_id), 100);
+}
+
+#[test]
+fn test_pause_blocks_sales_but_not_exits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let drawn_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    let refunded_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    client.buy_ticket(&drawn_round, &alice, &100, &create_commit_hash(&env, &contract_id, &seed, &alice, drawn_round));
+    client.buy_ticket(&refunded_round, &bob, &100, &create_commit_hash(&env, &contract_id, &seed, &bob, refunded_round));
+    
+    // Only the admin or a pauser can pull the brake
+    let pauser = Address::generate(&env);
+    let reason = String::from_str(&env, "investigating commit bug");
+    let result = client.try_pause(&pauser, &reason);
+    assert_eq!(result, Err(Ok(LotteryError::NotAuthorized)));
+    let result = client.try_unpause(&admin);
+    assert_eq!(result, Err(Ok(LotteryError::NotPaused)));
+    assert_eq!(client.pause_state(), None);
+    
+    client.grant_role(&pauser, &Role::Pauser);
+    client.pause(&pauser, &reason);
+    let expected = Paused {
+        reason: reason.clone(),
+        by: pauser.clone(),
+    };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    let state = client.pause_state().unwrap();
+    assert_eq!(state.reason, reason);
+    assert_eq!(state.paused_by, pauser);
+    assert_eq!(state.paused_at, env.ledger().timestamp());
+    
+    // Sales and new rounds are halted
+    let carol = Address::generate(&env);
+    token_admin_client.mint(&carol, &1000);
+    let commit = create_commit_hash(&env, &contract_id, &seed, &carol, drawn_round);
+    let result = client.try_buy_ticket(&drawn_round, &carol, &100, &commit);
+    assert_eq!(result, Err(Ok(LotteryError::ContractPaused)));
+    let result = client.try_buy_tickets(&drawn_round, &carol, &1, &vec![&env, commit]);
+    assert_eq!(result, Err(Ok(LotteryError::ContractPaused)));
+    let result = client.try_create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(result, Err(Ok(LotteryError::ContractPaused)));
+    
+    // Reveals, finalization, claims and refunds keep working
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&drawn_round, &alice, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    assert_eq!(client.finalize_round(&admin, &drawn_round), vec![&env, alice.clone()]);
+    assert_eq!(client.claim_prize(&drawn_round, &alice), 100);
+    
+    client.cancel_round(&refunded_round);
+    client.claim_refund(&refunded_round, &bob);
+    assert_eq!(token.balance(&bob), 1000);
+    
+    client.unpause(&pauser);
+    let expected = Unpaused { by: pauser.clone() };
+    assert_eq!(
+        contract_events(&env, &contract_id),
+        vec![&env, as_published(&env, &contract_id, &expected)]
+    );
+    assert_eq!(client.pause_state(), None);
+    client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+}
+
+#[test]
+fn test_participant_queries() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 10, 1, true));
+    
+    // Alice holds tickets 1, 3 and 4, Bob holds ticket 2
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    let seeds = [
+        Bytes::from_slice(&env, b"alice-1"),
+        Bytes::from_slice(&env, b"alice-2"),
+        Bytes::from_slice(&env, b"alice-3"),
+    ];
+    let alice_commits: Vec<BytesN<32>> = Vec::from_array(&env, [
+        create_commit_hash(&env, &contract_id, &seeds[0], &alice, round_id),
+        create_commit_hash(&env, &contract_id, &seeds[1], &alice, round_id),
+        create_commit_hash(&env, &contract_id, &seeds[2], &alice, round_id),
+    ]);
+    let bob_seed = Bytes::from_slice(&env, b"bob");
+    let bob_commit = create_commit_hash(&env, &contract_id, &bob_seed, &bob, round_id);
+    client.buy_ticket(&round_id, &alice, &10, &alice_commits.get(0).unwrap());
+    client.buy_ticket(&round_id, &bob, &10, &bob_commit);
+    client.buy_tickets(&round_id, &alice, &2, &alice_commits.slice(1..));
+    
+    assert_eq!(
+        client.get_participants(&round_id, &0, &10),
+        vec![&env, alice.clone(), bob.clone(), alice.clone(), alice.clone()]
+    );
+    assert_eq!(client.get_participants(&round_id, &1, &2), vec![&env, bob.clone(), alice.clone()]);
+    assert_eq!(client.get_participants(&round_id, &4, &10), Vec::new(&env));
+    assert_eq!(client.get_participants(&round_id, &u64::MAX, &u32::MAX), Vec::new(&env));
+    
+    assert_eq!(client.tickets_of(&round_id, &alice, &0, &10), vec![&env, 1, 3, 4]);
+    assert_eq!(client.tickets_of(&round_id, &alice, &1, &1), vec![&env, 3]);
+    assert_eq!(client.tickets_of(&round_id, &bob, &0, &10), vec![&env, 2]);
+    assert_eq!(client.tickets_of(&round_id, &admin, &0, &10), Vec::new(&env));
+    
+    assert_eq!(client.get_commit(&round_id, &alice), Some(alice_commits.get(0).unwrap()));
+    assert_eq!(client.get_commit(&round_id, &bob), Some(bob_commit.clone()));
+    assert_eq!(client.get_commit(&round_id, &admin), None);
+    
+    let ticket = client.get_ticket(&round_id, &2).unwrap();
+    assert_eq!(ticket.index, 2);
+    assert_eq!(ticket.owner, bob);
+    assert_eq!(ticket.commit, bob_commit);
+    assert_eq!(ticket.seed, None);
+    assert_eq!(client.get_ticket(&round_id, &5), None);
+    assert_eq!(client.get_ticket(&(round_id + 1), &1), None);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&round_id, &bob, &2, &bob_seed);
+    client.reveal_seed(&round_id, &alice, &1, &seeds[0]);
+    assert_eq!(client.get_ticket(&round_id, &2).unwrap().seed, Some(bob_seed));
+    assert!(client.has_revealed(&round_id, &bob));
+    
+    // Alice still has two unrevealed tickets
+    assert!(!client.has_revealed(&round_id, &alice));
+    client.reveal_seed(&round_id, &alice, &3, &seeds[1]);
+    client.reveal_seed(&round_id, &alice, &4, &seeds[2]);
+    assert!(client.has_revealed(&round_id, &alice));
+    assert!(!client.has_revealed(&round_id, &admin));
+}
+
+#[test]
+fn test_participant_queries_are_bounded() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 1, 1, true));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id);
+    let mut commits = Vec::new(&env);
+    for _ in 0..MAX_TICKETS_PER_CALL {
+        commits.push_back(commit.clone());
+    }
+    for _ in 0..150 / MAX_TICKETS_PER_CALL {
+        client.buy_tickets(&round_id, &participant, &MAX_TICKETS_PER_CALL, &commits);
+    }
+    
+    // A page never holds more than 100 entries, whatever the limit asked for
+    assert_eq!(client.get_participants(&round_id, &0, &1000).len(), 100);
+    assert_eq!(client.get_participants(&round_id, &100, &1000).len(), 50);
+    let tickets = client.tickets_of(&round_id, &participant, &0, &1000);
+    assert_eq!(tickets.len(), 100);
+    assert_eq!(tickets.get(99), Some(100));
+    assert_eq!(client.tickets_of(&round_id, &participant, &100, &1000).len(), 50);
+}
+
+#[test]
+fn test_player_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let shared_round = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, true));
+    let solo_round = client.create_round(&admin, &round_config(&env, &token.address, 50, 1, false));
+    
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    token_admin_client.mint(&alice, &1000);
+    token_admin_client.mint(&bob, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let alice_commit = create_commit_hash(&env, &contract_id, &seed, &alice, shared_round);
+    let bob_commit = create_commit_hash(&env, &contract_id, &seed, &bob, shared_round);
+    client.buy_tickets(&shared_round, &alice, &2, &vec![&env, alice_commit.clone(), alice_commit]);
+    client.buy_ticket(&shared_round, &bob, &100, &bob_commit);
+    client.buy_ticket(&solo_round, &alice, &50, &create_commit_hash(&env, &contract_id, &seed, &alice, solo_round));
+    
+    let stats = client.player_stats(&alice);
+    assert_eq!(stats.rounds_played, 2);
+    assert_eq!(stats.tickets_bought, 3);
+    assert_eq!(stats.total_spent.get(token.address.clone()), Some(250));
+    assert_eq!(stats.wins, 0);
+    assert_eq!(client.player_rounds(&alice, &0, &10), vec![&env, shared_round, solo_round]);
+    assert_eq!(client.player_rounds(&alice, &1, &10), vec![&env, solo_round]);
+    assert_eq!(client.player_rounds(&bob, &0, &10), vec![&env, shared_round]);
+    
+    // An address that never played has empty history
+    let stranger = client.player_stats(&admin);
+    assert_eq!(stranger.rounds_played, 0);
+    assert_eq!(stranger.total_spent.len(), 0);
+    assert_eq!(client.player_rounds(&admin, &0, &10), Vec::new(&env));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    client.reveal_seed(&shared_round, &alice, &1, &seed);
+    client.reveal_seed(&shared_round, &alice, &2, &seed);
+    client.reveal_seed(&shared_round, &bob, &3, &seed);
+    client.reveal_seed(&solo_round, &alice, &1, &seed);
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    let shared_winner = client.finalize_round(&admin, &shared_round).get(0).unwrap();
+    client.finalize_round(&admin, &solo_round);
+    
+    // Alice always wins the solo round; the shared round went to one of them
+    let alice_stats = client.player_stats(&alice);
+    let bob_stats = client.player_stats(&bob);
+    if shared_winner == alice {
+        assert_eq!(alice_stats.wins, 2);
+        assert_eq!(alice_stats.total_winnings.get(token.address.clone()), Some(350));
+        assert_eq!(bob_stats.wins, 0);
+        assert_eq!(bob_stats.total_winnings.get(token.address.clone()), None);
+    } else {
+        assert_eq!(alice_stats.wins, 1);
+        assert_eq!(alice_stats.total_winnings.get(token.address.clone()), Some(50));
+        assert_eq!(bob_stats.wins, 1);
+        assert_eq!(bob_stats.total_winnings.get(token.address.clone()), Some(300));
+    }
+    assert_eq!(bob_stats.rounds_played, 1);
+    assert_eq!(bob_stats.tickets_bought, 1);
+    assert_eq!(bob_stats.total_spent.get(token.address.clone()), Some(100));
+}
+
+#[test]
+fn test_round_status_and_listing() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    assert_eq!(client.round_status(&1), None);
+    assert_eq!(client.list_rounds(&0, &10, &None).len(), 0);
+    
+    let drawn = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    let cancelled = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    assert_eq!(client.round_status(&drawn), Some(RoundStatus::Sale));
+    
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    client.buy_ticket(&drawn, &participant, &100, &create_commit_hash(&env, &contract_id, &seed, &participant, drawn));
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    assert_eq!(client.round_status(&drawn), Some(RoundStatus::Reveal));
+    client.reveal_seed(&drawn, &participant, &1, &seed);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    assert_eq!(client.round_status(&drawn), Some(RoundStatus::Finalizable));
+    client.finalize_round(&admin, &drawn);
+    client.cancel_round(&cancelled);
+    assert_eq!(client.round_status(&drawn), Some(RoundStatus::Finalized));
+    assert_eq!(client.round_status(&cancelled), Some(RoundStatus::Cancelled));
+    
+    let revealing = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    let selling = client.create_round(&admin, &round_config(&env, &token.address, 100, 48, false));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 2 * 3600;
+    });
+    
+    assert_eq!(client.round_count(), 4);
+    let all = client.list_rounds(&0, &10, &None);
+    assert_eq!(all.len(), 4);
+    assert_eq!(all.get(3).unwrap().round_id, selling);
+    let page = client.list_rounds(&2, &1, &None);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().round_id, revealing);
+    
+    let ids = |rounds: Vec<LotteryRound>| {
+        let mut ids = Vec::new(&env);
+        for round in rounds.iter() {
+            ids.push_back(round.round_id);
+        }
+        ids
+    };
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Sale))), vec![&env, selling]);
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Reveal))), vec![&env, revealing]);
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Finalized))), vec![&env, drawn]);
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Cancelled))), vec![&env, cancelled]);
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Finalizable))), Vec::new(&env));
+    
+    // Status follows the ledger clock without any call changing the round
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    assert_eq!(ids(client.list_rounds(&0, &10, &Some(RoundStatus::Finalizable))), vec![&env, revealing]);
+    assert_eq!(client.round_status(&selling), Some(RoundStatus::Sale));
+}
+
+#[test]
+fn test_get_round_distinguishes_missing_rounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, _) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, false));
+    
+    let round = client.get_round(&round_id).unwrap();
+    assert_eq!(round.round_id, round_id);
+    assert_eq!(round.ticket_price, 100);
+    assert!(client.get_round(&(round_id + 1)).is_none());
+    
+    // The old view still answers with a zeroed round for compatibility
+    assert_eq!(client.view_round(&(round_id + 1)).round_id, 0);
+    
+    let participant = Address::generate(&env);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let commit = create_commit_hash(&env, &contract_id, &seed, &participant, round_id + 1);
+    let result = client.try_buy_ticket(&(round_id + 1), &participant, &100, &commit);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotFound)));
+    let result = client.try_reveal_seed(&(round_id + 1), &participant, &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotFound)));
+    let result = client.try_claim_prize(&(round_id + 1), &participant);
+    assert_eq!(result, Err(Ok(LotteryError::RoundNotFound)));
+    assert_eq!(client.get_participants(&(round_id + 1), &0, &10).len(), 0);
+}
+
+#[test]
+fn test_uniform_index_rejects_biased_words() {
+    // With bound = 2^63 + 1 a plain modulo would map words below 2^63 - 1
+    // and words above 2^63 + 1 onto the same low indexes
+    let bound = (1u64 << 63) + 1;
+    assert_eq!(randomness::reduce(0, bound), None);
+    assert_eq!(randomness::reduce((1u64 << 63) - 2, bound), None);
+    assert_eq!(randomness::reduce((1u64 << 63) - 1, bound), Some((1u64 << 63) - 1));
+    assert_eq!(randomness::reduce(u64::MAX, bound), Some((1u64 << 63) - 2));
+    
+    // Powers of two and tiny bounds never reject
+    assert_eq!(randomness::reduce(0, 1), Some(0));
+    assert_eq!(randomness::reduce(5, 4), Some(1));
+    assert_eq!(randomness::reduce(u64::MAX, 3), Some(0));
+}
+
+#[test]
+fn test_uniform_index_distribution() {
+    let env = Env::default();
+    let samples = 3000u32;
+    
+    // bound = 3 * 2^62: a modulo over the first 8 bytes would put half of
+    // all draws in the lowest third instead of a third of them
+    let bound = 3u64 << 62;
+    let mut low_third = 0u32;
+    // Small bound: every index should get close to a seventh of the draws
+    let mut counts = [0u32; 7];
+    for i in 0..samples {
+        let hash: BytesN<32> = env.crypto().sha256(&Bytes::from_slice(&env, &i.to_be_bytes())).into();
+        if randomness::uniform_index(&env, &hash, bound) < 1u64 << 62 {
+            low_third += 1;
+        }
+        let index = randomness::uniform_index(&env, &hash, 7);
+        counts[index as usize] += 1;
+    }
+    
+    // Expected 1000 with a standard deviation of about 26
+    assert!((900..=1100).contains(&low_third), "low third drew {}", low_third);
+    
+    // Chi-square with 6 degrees of freedom; 22.46 is the 0.1% critical value
+    let expected = samples as f64 / 7.0;
+    let chi_square: f64 = counts.iter().map(|&c| (c as f64 - expected).powi(2) / expected).sum();
+    assert!(chi_square < 22.46, "chi-square {} for counts {:?}", chi_square, counts);
+}
+
+#[test]
+fn test_draw_slots_distribution() {
+    let env = Env::default();
+    let samples = 6000u32;
+    
+    // Two prize tiers over five eligible tickets: each of the 20 ordered
+    // (1st, 2nd) pairs should come up equally often, which also checks the
+    // second draw steps over the first winner without favouring its
+    // neighbours. finalize_round draws through draw_slots, as test_verify_draw
+    // confirms against the off-chain verifier, and
+    // test_winner_distribution_over_many_rounds checks it end to end.
+    let mut counts = [[0u32; 5]; 5];
+    for i in 0..samples {
+        // Each sample is one draw's worth of work, so it gets a fresh budget
+        env.cost_estimate().budget().reset_default();
+        let hash: BytesN<32> = env.crypto().sha256(&Bytes::from_slice(&env, &i.to_be_bytes())).into();
+        let slots = randomness::draw_slots(&env, &hash, 5, 2);
+        let (first, second) = (slots.get(0).unwrap(), slots.get(1).unwrap());
+        assert_ne!(first, second);
+        counts[first as usize - 1][second as usize - 1] += 1;
+    }
+    
+    // Chi-square with 19 degrees of freedom; 43.82 is the 0.1% critical value
+    let expected = samples as f64 / 20.0;
+    let mut chi_square = 0.0;
+    for (first, row) in counts.iter().enumerate() {
+        for (second, &count) in row.iter().enumerate() {
+            if first != second {
+                chi_square += (count as f64 - expected).powi(2) / expected;
+            }
+        }
+    }
+    assert!(chi_square < 43.82, "chi-square {} for counts {:?}", chi_square, counts);
+}
+
+#[test]
+fn test_winner_distribution_over_many_rounds() {
+    let rounds = 150u32;
+    
+    // One player holds all three tickets; count which ticket index wins when
+    // each round goes through reveal_seed and finalize_round. Every round
+    // runs in a fresh Env, so the run time stays flat as rounds pile up.
+    let mut wins = [0u32; 3];
+    for r in 0..rounds {
+        // Snapshot capture is disabled: the ledger dump would be written per round
+        let env = Env::new_with_config(EnvTestConfig {
+            capture_snapshot_at_drop: false,
+        });
+        env.mock_all_auths();
+        
+        let admin = Address::generate(&env);
+        let token_admin = Address::generate(&env);
+        let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+        
+        let contract_id = env.register(LotteryContract, ());
+        let client = LotteryContractClient::new(&env, &contract_id);
+        
+        client.init_admin(&admin, &token.address);
+        let player = Address::generate(&env);
+        token_admin_client.mint(&player, &3);
+        
+        let round_id = client.create_round(&admin, &round_config(&env, &token.address, 1, 1, true));
+        let mut seeds = Vec::new(&env);
+        let mut commits = Vec::new(&env);
+        for t in 0..3u32 {
+            let seed = Bytes::from_slice(&env, &[r.to_be_bytes(), t.to_be_bytes()].concat());
+            commits.push_back(create_commit_hash(&env, &contract_id, &seed, &player, round_id));
+            seeds.push_back(seed);
+        }
+        client.buy_tickets(&round_id, &player, &3, &commits);
+        
+        pass_time(&env, 3600);
+        for (t, seed) in seeds.iter().enumerate() {
+            client.reveal_seed(&round_id, &player, &(t as u64 + 1), &seed);
+        }
+        pass_time(&env, 24 * 3600);
+        client.finalize_round(&admin, &round_id);
+        let winning_ticket = client.get_round(&round_id).unwrap().winning_tickets.get(0).unwrap();
+        wins[winning_ticket as usize - 1] += 1;
+    }
+    
+    // Chi-square with 2 degrees of freedom; 13.82 is the 0.1% critical value
+    let expected = rounds as f64 / 3.0;
+    let chi_square: f64 = wins.iter().map(|&w| (w as f64 - expected).powi(2) / expected).sum();
+    assert!(chi_square < 13.82, "chi-square {} for wins {:?}", chi_square, wins);
+}
+
+#[test]
+fn test_entropy_modes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    
+    // PRNG-only rounds collect no reveals, so there is nothing to bond
+    let result = client.try_create_round(&admin, &RoundConfig {
+        entropy_mode: EntropyMode::Prng,
+        reveal_policy: RevealPolicy::Bond(10),
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    assert_eq!(result, Err(Ok(LotteryError::InvalidRevealPolicy)));
+    
+    let mixed = client.create_round(&admin, &RoundConfig {
+        entropy_mode: EntropyMode::CommitRevealPrng,
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    let prng_only = client.create_round(&admin, &RoundConfig {
+        entropy_mode: EntropyMode::Prng,
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    assert_eq!(client.get_round(&prng_only).unwrap().entropy_mode, EntropyMode::Prng);
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut players = Vec::new(&env);
+    for _ in 0..2 {
+        let player = Address::generate(&env);
+        token_admin_client.mint(&player, &1000);
+        client.buy_ticket(&mixed, &player, &100, &create_commit_hash(&env, &contract_id, &seed, &player, mixed));
+        client.buy_ticket(&prng_only, &player, &100, &create_commit_hash(&env, &contract_id, &seed, &player, prng_only));
+        players.push_back(player);
+    }
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    let player = players.get(0).unwrap();
+    client.reveal_seed(&mixed, &player, &1, &seed);
+    let result = client.try_reveal_seed(&prng_only, &player, &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::RevealNotUsed)));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // Commit-reveal plus PRNG: the final hash chains the seeds, the ledger
+    // sequence and the PRNG output, all of which are published
+    client.finalize_round(&admin, &mixed);
+    let events = contract_events(&env, &contract_id);
+    let (_, _, data) = events.get(0).unwrap();
+    let inputs: Map<Symbol, Val> = data.into_val(&env);
+    let seed_hash: Option<BytesN<32>> = inputs.get(Symbol::new(&env, "seed_hash")).unwrap().into_val(&env);
+    let sequence: Option<u32> = inputs.get(Symbol::new(&env, "ledger_sequence")).unwrap().into_val(&env);
+    let prng_output: Option<BytesN<32>> = inputs.get(Symbol::new(&env, "prng_output")).unwrap().into_val(&env);
+    let final_hash: BytesN<32> = inputs.get(Symbol::new(&env, "final_hash")).unwrap().into_val(&env);
+    
+    let mut seed_input = Bytes::from_array(&env, &[0u8; 32]);
+    seed_input.append(&seed);
+    let expected_seed_hash: BytesN<32> = env.crypto().sha256(&seed_input).into();
+    assert_eq!(seed_hash, Some(expected_seed_hash.clone()));
+    assert_eq!(sequence, Some(env.ledger().sequence()));
+    let mut with_sequence = Bytes::from_array(&env, &expected_seed_hash.to_array());
+    with_sequence.append(&Bytes::from_slice(&env, &env.ledger().sequence().to_be_bytes()));
+    let with_sequence: BytesN<32> = env.crypto().sha256(&with_sequence).into();
+    let mut with_prng = Bytes::from_array(&env, &with_sequence.to_array());
+    with_prng.append(&Bytes::from_array(&env, &prng_output.unwrap().to_array()));
+    let expected_final: BytesN<32> = env.crypto().sha256(&with_prng).into();
+    assert_eq!(final_hash, expected_final);
+    
+    // PRNG only: no reveals are needed and every ticket is eligible
+    let winner = client.finalize_round(&admin, &prng_only).get(0).unwrap();
+    assert!(players.contains(&winner));
+    let events = contract_events(&env, &contract_id);
+    let (_, _, data) = events.get(0).unwrap();
+    let inputs: Map<Symbol, Val> = data.into_val(&env);
+    let seed_hash: Option<BytesN<32>> = inputs.get(Symbol::new(&env, "seed_hash")).unwrap().into_val(&env);
+    let sequence: Option<u32> = inputs.get(Symbol::new(&env, "ledger_sequence")).unwrap().into_val(&env);
+    let prng_output: Option<BytesN<32>> = inputs.get(Symbol::new(&env, "prng_output")).unwrap().into_val(&env);
+    let final_hash: BytesN<32> = inputs.get(Symbol::new(&env, "final_hash")).unwrap().into_val(&env);
+    assert_eq!(seed_hash, None);
+    assert_eq!(sequence, None);
+    assert_eq!(prng_output, Some(final_hash));
+}
+
+// Helper function to read a round's draw record into the off-chain verifier's types
+fn offchain_record(record: &DrawRecord) -> draw_verifier::DrawRecord {
+    draw_verifier::DrawRecord {
+        entropy_mode: match record.entropy_mode {
+            EntropyMode::CommitReveal => draw_verifier::EntropyMode::CommitReveal,
+            EntropyMode::CommitRevealPrng => draw_verifier::EntropyMode::CommitRevealPrng,
+            EntropyMode::Prng => draw_verifier::EntropyMode::Prng,
+            EntropyMode::Provider => draw_verifier::EntropyMode::Provider,
+        },
+        combined_hash: record.combined_hash.to_array(),
+        ledger_sequence: record.ledger_sequence,
+        prng_output: record.prng_output.as_ref().map(|output| output.to_array()),
+        final_hash: record.final_hash.to_array(),
+        reveal_count: record.reveal_count,
+        eligible_count: record.eligible_count,
+        revealed_only: record.revealed_only,
+        winning_tickets: record.winning_tickets.iter().collect(),
+    }
+}
+
+// Helper function to read a round's reveals, in reveal order, the way an off-chain verifier would
+fn offchain_reveals(client: &LotteryContractClient, round_id: u64) -> std::vec::Vec<draw_verifier::Reveal> {
+    let mut reveals = std::vec::Vec::new();
+    loop {
+        let page = client.get_reveals(&round_id, &(reveals.len() as u64), &MAX_PAGE_SIZE);
+        if page.is_empty() {
+            return reveals;
+        }
+        for step in page.iter() {
+            let ticket = client.get_ticket(&round_id, &step.ticket_index).unwrap();
+            reveals.push(draw_verifier::Reveal {
+                ticket_index: step.ticket_index,
+                seed: ticket.seed.unwrap().iter().collect(),
+            });
+        }
+    }
+}
+
+#[test]
+fn test_verify_draw() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let tiers = vec![&env, 7_000u32, 3_000u32];
+    let exclude = client.create_round(&admin, &RoundConfig { prize_tiers: tiers.clone(), ..round_config(&env, &token.address, 100, 1, false) });
+    let bond = client.create_round(&admin, &RoundConfig {
+        prize_tiers: tiers.clone(),
+        reveal_policy: RevealPolicy::Bond(50),
+        entropy_mode: EntropyMode::CommitRevealPrng,
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    let prng_only = client.create_round(&admin, &RoundConfig {
+        prize_tiers: tiers.clone(),
+        entropy_mode: EntropyMode::Prng,
+        ..round_config(&env, &token.address, 100, 1, false)
+    });
+    let rounds = [exclude, bond, prng_only];
+    
+    let mut players = Vec::new(&env);
+    for i in 0..4u32 {
+        let player = Address::generate(&env);
+        token_admin_client.mint(&player, &1000);
+        let seed = Bytes::from_slice(&env, &i.to_be_bytes());
+        for round_id in rounds {
+            client.buy_ticket(&round_id, &player, &100, &create_commit_hash(&env, &contract_id, &seed, &player, round_id));
+        }
+        players.push_back(player);
+    }
+    
+    // Nothing is recorded until the round is drawn
+    assert_eq!(client.get_draw(&exclude), None);
+    assert_eq!(client.try_verify_draw(&exclude), Err(Ok(LotteryError::DrawNotRecorded)));
+    assert_eq!(client.try_verify_draw(&99), Err(Ok(LotteryError::RoundNotFound)));
+    
+    // Ticket 2 withholds its seed in both commit-reveal rounds
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for i in [0u32, 2, 3] {
+        let seed = Bytes::from_slice(&env, &i.to_be_bytes());
+        for round_id in [exclude, bond] {
+            client.reveal_seed(&round_id, &players.get(i).unwrap(), &(i as u64 + 1), &seed);
+        }
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    for round_id in rounds {
+        client.finalize_round(&admin, &round_id);
+        let record = client.get_draw(&round_id).unwrap();
+        assert_eq!(record.winning_tickets, client.get_round(&round_id).unwrap().winning_tickets);
+        assert!(client.verify_draw(&round_id));
+        
+        // The off-chain verifier reaches the same winners from the same record
+        let offchain = offchain_record(&record);
+        assert_eq!(draw_verifier::verify(&offchain, &offchain_reveals(&client, round_id)), Ok(offchain.winning_tickets.clone()));
+    }
+    
+    // Reveals are listed, and chained, in the order they were made
+    let reveals = client.get_reveals(&exclude, &0, &10);
+    assert_eq!(reveals.len(), 3);
+    assert_eq!(reveals.iter().map(|step| step.ticket_index).collect::<std::vec::Vec<u64>>(), [1, 3, 4]);
+    assert_eq!(client.get_reveals(&exclude, &1, &1).get(0).unwrap(), reveals.get(1).unwrap());
+    assert_eq!(client.get_reveals(&prng_only, &0, &10).len(), 0);
+    
+    let record = client.get_draw(&exclude).unwrap();
+    assert_eq!(record.combined_hash, reveals.get(2).unwrap().seed_hash);
+    assert_eq!(record.eligible_count, 3);
+    assert!(record.revealed_only);
+    assert!(!record.winning_tickets.contains(2u64));
+    
+    let record = client.get_draw(&bond).unwrap();
+    assert_eq!(record.reveal_count, 3);
+    assert_eq!(record.eligible_count, 4);
+    assert!(!record.revealed_only);
+    assert!(record.prng_output.is_some());
+    
+    let record = client.get_draw(&prng_only).unwrap();
+    assert_eq!(record.reveal_count, 0);
+    assert_eq!(record.ledger_sequence, None);
+    assert_eq!(Some(record.final_hash.clone()), record.prng_output);
+    
+    // A record that no longer matches its inputs fails verification
+    let mut tampered = client.get_draw(&exclude).unwrap();
+    tampered.combined_hash = BytesN::from_array(&env, &[1u8; 32]);
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&DrawBook::Draw(exclude), &tampered);
+    });
+    assert!(!client.verify_draw(&exclude));
+    assert!(draw_verifier::verify(&offchain_record(&tampered), &offchain_reveals(&client, exclude)).is_err());
+    
+    // So does one that would draw past the round's tickets, without panicking
+    let original = client.get_draw(&bond).unwrap();
+    let mut tampered = original.clone();
+    tampered.eligible_count = 10;
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&DrawBook::Draw(bond), &tampered);
+    });
+    assert!(!client.verify_draw(&bond));
+    
+    // The winners' seeds are checked against their commits again
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&DrawBook::Draw(bond), &original);
+    });
+    assert!(client.verify_draw(&bond));
+    let revealed_winner = original.winning_tickets.iter().find(|&ticket| ticket != 2).unwrap();
+    env.as_contract(&contract_id, || {
+        env.storage().persistent().set(&RevealBook::Reveal(bond, revealed_winner), &Bytes::from_slice(&env, b"forged"));
+    });
+    assert!(!client.verify_draw(&bond));
+}
+
+#[test]
+fn test_commit_binds_contract_and_round() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    
+    client.init_admin(&admin, &token.address);
+    let round_id = client.create_round(&admin, &round_config(&env, &token.address, 100, 1, true));
+    assert_eq!(client.get_round(&round_id).unwrap().commit_scheme, CommitScheme::V2);
+    
+    // The same seed committed for another deployment, another round or
+    // another address does not open this round's ticket
+    let participant = Address::generate(&env);
+    token_admin_client.mint(&participant, &1000);
+    let seed = Bytes::from_slice(&env, b"seed");
+    let other_contract = Address::generate(&env);
+    let commits = vec![
+        &env,
+        create_commit_hash(&env, &other_contract, &seed, &participant, round_id),
+        create_commit_hash(&env, &contract_id, &seed, &participant, round_id + 1),
+        create_commit_hash(&env, &contract_id, &seed, &admin, round_id),
+        create_commit_hash(&env, &contract_id, &seed, &participant, round_id),
+    ];
+    client.buy_tickets(&round_id, &participant, &4, &commits);
+    
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    for ticket_index in 1..=3u64 {
+        let result = client.try_reveal_seed(&round_id, &participant, &ticket_index, &seed);
+        assert_eq!(result, Err(Ok(LotteryError::CommitMismatch)));
+    }
+    client.reveal_seed(&round_id, &participant, &4, &seed);
+    assert_eq!(client.get_ticket(&round_id, &4).unwrap().seed, Some(seed));
+}
+
+#[test]
+fn test_randomness_provider() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    let provider_id = env.register(MockRandomnessProvider, ());
+    let provider = MockRandomnessProviderClient::new(&env, &provider_id);
+    let key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    
+    client.init_admin(&admin, &token.address);
+    client.set_keeper_reward(&100);
+    let config = RoundConfig { entropy_mode: EntropyMode::Provider, ..round_config(&env, &token.address, 100, 1, false) };
+    assert_eq!(client.try_create_round(&admin, &config), Err(Ok(LotteryError::ProviderNotConfigured)));
+    
+    let registered = RandomnessProvider {
+        contract: provider_id.clone(),
+        public_key: BytesN::from_array(&env, &key.verifying_key().to_bytes()),
+    };
+    client.set_randomness_provider(&Some(registered.clone()));
+    let result = client.try_create_round(&admin, &RoundConfig { reveal_policy: RevealPolicy::Bond(10), ..config.clone() });
+    assert_eq!(result, Err(Ok(LotteryError::InvalidRevealPolicy)));
+    let round_id = client.create_round(&admin, &config);
+    assert_eq!(client.round_provider(&round_id), Some(registered.clone()));
+    
+    let seed = Bytes::from_slice(&env, b"seed");
+    let mut players = Vec::new(&env);
+    for _ in 0..3 {
+        let player = Address::generate(&env);
+        token_admin_client.mint(&player, &1000);
+        client.buy_ticket(&round_id, &player, &100, &create_commit_hash(&env, &contract_id, &seed, &player, round_id));
+        players.push_back(player);
+    }
+    
+    // Provider rounds take no reveals, like PRNG-only rounds
+    env.ledger().with_mut(|li| {
+        li.timestamp += 3600;
+    });
+    let result = client.try_reveal_seed(&round_id, &players.get(0).unwrap(), &1, &seed);
+    assert_eq!(result, Err(Ok(LotteryError::RevealNotUsed)));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    
+    // The round stays with the provider it was created with, whatever the
+    // admin configures later
+    let other_key = ed25519_dalek::SigningKey::from_bytes(&[8u8; 32]);
+    client.set_randomness_provider(&Some(RandomnessProvider {
+        contract: Address::generate(&env),
+        public_key: BytesN::from_array(&env, &other_key.verifying_key().to_bytes()),
+    }));
+    
+    // Finalizing sends the request and draws nothing yet
+    let keeper = Address::generate(&env);
+    assert_eq!(client.finalize_round(&keeper, &round_id), Vec::new(&env));
+    let events = contract_events(&env, &contract_id);
+    let request = client.randomness_request(&round_id).unwrap();
+    assert_eq!(request.requested_by, keeper);
+    assert_eq!(request.provider, registered);
+    assert_eq!(provider.last_request(&round_id), Some((contract_id.clone(), request.request_id.clone())));
+    let expected = RandomnessRequested { round_id, provider: provider_id.clone(), request_id: request.request_id.clone() };
+    assert_eq!(events, vec![&env, as_published(&env, &contract_id, &expected)]);
+    assert!(!client.get_round(&round_id).unwrap().finalized);
+    assert_eq!(client.try_finalize_round(&keeper, &round_id), Err(Ok(LotteryError::RandomnessPending)));
+    
+    // A signature over another request, or with another key, is rejected
+    let wrong_request = BytesN::from_array(&env, &[0u8; 32]);
+    assert!(provider.try_fulfill(&round_id, &sign_request(&env, &key, &wrong_request)).is_err());
+    assert!(provider.try_fulfill(&round_id, &sign_request(&env, &other_key, &request.request_id)).is_err());
+    
+    // Only the provider may deliver, even with a valid signature
+    let signature = sign_request(&env, &key, &request.request_id);
+    env.set_auths(&[]);
+    assert!(client.try_fulfill_randomness(&round_id, &signature).is_err());
+    env.mock_all_auths();
+    
+    // The provider's answer draws the round with sha256(signature) and pays
+    // the keeper who asked. The provider signs only the request ID, so it
+    // has no value to choose.
+    let randomness: BytesN<32> = env.crypto().sha256(&Bytes::from_array(&env, &signature.to_array())).into();
+    let winners = provider.fulfill(&round_id, &signature);
+    let expected = RandomnessFulfilled {
+        round_id,
+        request_id: request.request_id.clone(),
+        randomness: randomness.clone(),
+        signature: signature.clone(),
+    };
+    assert!(contract_events(&env, &contract_id).contains(as_published(&env, &contract_id, &expected)));
+    assert_eq!(winners.len(), 1);
+    assert!(players.contains(winners.get(0).unwrap()));
+    assert_eq!(token.balance(&keeper), 3);
+    assert_eq!(client.randomness_request(&round_id), None);
+    
+    let round = client.get_round(&round_id).unwrap();
+    assert!(round.finalized);
+    let record = client.get_draw(&round_id).unwrap();
+    assert_eq!(record.entropy_mode, EntropyMode::Provider);
+    assert_eq!(record.ledger_sequence, None);
+    assert_eq!(record.prng_output, Some(randomness.clone()));
+    assert_eq!(record.final_hash, randomness);
+    assert!(client.verify_draw(&round_id));
+    assert!(draw_verifier::verify(&offchain_record(&record), &offchain_reveals(&client, round_id)).is_ok());
+    
+    // The request is spent, so the answer cannot be replayed
+    assert!(provider.try_fulfill(&round_id, &signature).is_err());
+    assert_eq!(client.try_finalize_round(&keeper, &round_id), Err(Ok(LotteryError::RoundInactive)));
+}
+
+#[test]
+fn test_randomness_request_times_out() {
+    let env = Env::default();
+    env.mock_all_auths();
+    
+    let admin = Address::generate(&env);
+    let token_admin = Address::generate(&env);
+    let (token, token_admin_client) = create_token_contract(&env, &token_admin);
+    
+    let contract_id = env.register(LotteryContract, ());
+    let client = LotteryContractClient::new(&env, &contract_id);
+    let slow_id = env.register(MockRandomnessProvider, ());
+    let slow = MockRandomnessProviderClient::new(&env, &slow_id);
+    let slow_key = ed25519_dalek::SigningKey::from_bytes(&[7u8; 32]);
+    let backup_id = env.register(MockRandomnessProvider, ());
+    let backup_key = ed25519_dalek::SigningKey::from_bytes(&[9u8; 32]);
+    
+    client.init_admin(&admin, &token.address);
+    client.set_randomness_provider(&Some(RandomnessProvider {
+        contract: slow_id.clone(),
+        public_key: BytesN::from_array(&env, &slow_key.verifying_key().to_bytes()),
+    }));
+    let config = RoundConfig {
+        entropy_mode: EntropyMode::Provider,
+        ..round_config(&env, &token.address, 100, 1, false)
+    };
+    let answered = client.create_round(&admin, &config);
+    let abandoned = client.create_round(&admin, &config);
+    
+    let player = Address::generate(&env);
+    token_admin_client.mint(&player, &1000);
+    for round_id in [answered, abandoned] {
+        client.buy_ticket(&round_id, &player, &100, &create_commit_hash(&env, &contract_id, &Bytes::new(&env), &player, round_id));
+    }
+    env.ledger().with_mut(|li| {
+        li.timestamp += 25 * 3600;
+    });
+    client.finalize_round(&admin, &answered);
+    client.finalize_round(&admin, &abandoned);
+    let first = client.randomness_request(&answered).unwrap();
+    
+    // The provider is slow; once the request times out anyone can ask again,
+    // and the new request still goes to the round's own provider even after
+    // the admin switches providers
+    env.ledger().with_mut(|li| {
+        li.sequence_number += 719;
+    });
+    assert_eq!(client.try_finalize_round(&admin, &answered), Err(Ok(LotteryError::RandomnessPending)));
+    env.ledger().with_mut(|li| {
+        li.sequence_number += 1;
+    });
+    client.set_randomness_provider(&Some(RandomnessProvider {
+        contract: backup_id.clone(),
+        public_key: BytesN::from_array(&env, &backup_key.verifying_key().to_bytes()),
+    }));
+    let keeper = Address::generate(&env);
+    client.finalize_round(&keeper, &answered);
+    let second = client.randomness_request(&answered).unwrap();
+    assert_ne!(second.request_id, first.request_id);
+    assert_eq!(second.provider.contract, slow_id);
+    
+    // A late answer to the first request no longer counts
+    assert!(slow.try_fulfill(&answered, &sign_request(&env, &slow_key, &first.request_id)).is_err());
+    assert_eq!(slow.fulfill(&answered, &sign_request(&env, &slow_key, &second.request_id)), vec![&env, player.clone()]);
+    
+    // A round its provider never answers can be cancelled by anyone once the
+    // grace period after its reveal deadline runs out, and is refunded
+    assert_eq!(client.try_cancel_round(&abandoned), Err(Ok(LotteryError::RoundNotCancellable)));
+    env.ledger().with_mut(|li| {
+        li.timestamp += 24 * 3600;
+    });
+    env.set_auths(&[]);
+    client.cancel_round(&abandoned);
+    env.mock_all_auths();
+    assert_eq!(client.randomness_request(&abandoned), None);
+    assert_eq!(client.claim_refund(&abandoned, &player), 100);
+    
+    // New rounds use the provider configured when they are created
+    let round_id = client.create_round(&admin, &config);
+    assert_eq!(client.round_provider(&round_id).unwrap().contract, backup_id);
+    
+    // Without a provider, new Provider rounds cannot be created
+    client.set_randomness_provider(&None);
+    assert_eq!(client.randomness_provider(), None);
+    assert_eq!(client.try_create_round(&admin, &config), Err(Ok(LotteryError::ProviderNotConfigured)));
+}
@@ -1,15 +1,16 @@
 import React, { useState } from 'react';
 import { CONFIG } from '../config';
 import { LotteryRound } from '../utils/contract';
-import { getStoredSeed, bytesToHex } from '../utils/commitReveal';
+import { RevealableTicket } from '../utils/commitReveal';
 
 interface CLIInstructionsProps {
   round: LotteryRound;
   walletAddress: string | null;
   operation: 'buy_ticket' | 'reveal_seed';
+  tickets?: RevealableTicket[]; // the wallet's unrevealed tickets with stored seeds, for reveal_seed
 }
 
-const CLIInstructions: React.FC<CLIInstructionsProps> = ({ round, walletAddress, operation }) => {
+const CLIInstructions: React.FC<CLIInstructionsProps> = ({ round, walletAddress, operation, tickets = [] }) => {
   const [copied, setCopied] = useState(false);
 
   const copyToClipboard = (text: string) => {
@@ -98,24 +99,26 @@ $seedHex = ($bytes | ForEach-Object { $_.ToString("X2") }) -join ''
   }
 
   if (operation === 'reveal_seed') {
-    const storedSeed = walletAddress ? getStoredSeed(round.round_id) : null;
-    const seedHex = storedSeed ? bytesToHex(storedSeed) : "YOUR_STORED_SEED_HEX";
-
-    const revealCommand = `stellar contract invoke \\
+    // reveal_seed opens one ticket at a time, so there is a command per ticket
+    const revealCommand = (ticketIndex: string, seedHex: string) => `stellar contract invoke \\
   --id ${CONFIG.LOTTERY_CONTRACT_ID} \\
   --source YOUR_KEY_NAME \\
   --network ${CONFIG.NETWORK} \\
   -- reveal_seed \\
   --round_id ${round.round_id} \\
   --participant ${walletAddress} \\
+  --ticket_index ${ticketIndex} \\
   --seed ${seedHex}`;
+    const revealCommands = tickets.length > 0
+      ? tickets.map((ticket) => ({ label: `Ticket #${ticket.index}`, command: revealCommand(String(ticket.index), ticket.seed) }))
+      : [{ label: 'Your ticket', command: revealCommand('YOUR_TICKET_INDEX', 'YOUR_STORED_SEED_HEX') }];
 
     return (
       <div className="cli-instructions">
         <h3>📋 CLI Instructions for Revealing Seed</h3>
-        {storedSeed ? (
+        {tickets.length > 0 ? (
           <div className="info-message">
-            ✅ Seed found in browser storage: <code>{seedHex.slice(0, 16)}...</code>
+            ✅ Seeds found in browser storage for {tickets.length} ticket(s)
           </div>
         ) : (
           <div className="warning-message">
@@ -123,16 +126,19 @@ $seedHex = ($bytes | ForEach-Object { $_.ToString("X2") }) -join ''
           </div>
         )}
         
-        <div className="command-section">
-          <p>Replace <code>YOUR_KEY_NAME</code> with your key name:</p>
-          <button
-            onClick={() => copyToClipboard(revealCommand)}
-            className="copy-button"
-          >
-            {copied ? '✓ Copied!' : '📋 Copy Command'}
-          </button>
-          <pre className="command-block">{revealCommand}</pre>
-        </div>
+        {revealCommands.map(({ label, command }) => (
+          <div className="command-section" key={label}>
+            <h4>{label}</h4>
+            <p>Replace <code>YOUR_KEY_NAME</code> with your key name:</p>
+            <button
+              onClick={() => copyToClipboard(command)}
+              className="copy-button"
+            >
+              {copied ? '✓ Copied!' : '📋 Copy Command'}
+            </button>
+            <pre className="command-block">{command}</pre>
+          </div>
+        ))}
       </div>
     );
   }
@@ -1,8 +1,8 @@
 import React, { useState, useEffect } from 'react';
 import { LotteryRound } from '../utils/contract';
-import { buyTicket, revealSeed, submitTransaction, approveToken, getTokenBalance } from '../utils/contract';
+import { buyTicket, revealSeed, submitTransaction, approveToken, getTokenBalance, getTicketsOf, getTicket } from '../utils/contract';
 import { signTransactionWithWallet } from '../utils/wallet';
-import { generateSeed, computeCommitHash, storeSeed, getStoredSeed, bytesToHex, hexToBytes, removeStoredSeed } from '../utils/commitReveal';
+import { generateSeed, computeCommitHash, storeSeed, getStoredSeeds, bytesToHex, removeStoredSeed, RevealableTicket } from '../utils/commitReveal';
 import { CONFIG } from '../config';
 import CLIInstructions from './CLIInstructions';
 
@@ -19,10 +19,12 @@ const RoundDisplay: React.FC<RoundDisplayProps> = ({ round, walletAddress, onRef
   const [success, setSuccess] = useState<string | null>(null);
   const [tokenBalance, setTokenBalance] = useState<bigint | null>(null);
   const [loadingBalance, setLoadingBalance] = useState(false);
+  const [revealableTickets, setRevealableTickets] = useState<RevealableTicket[]>([]);
+  const [loadingTickets, setLoadingTickets] = useState(false);
 
   const currentTime = Math.floor(Date.now() / 1000);
   const isRoundActive = round.is_active && currentTime < round.end_time;
-  const isRevealPhase = !round.is_active && currentTime >= round.end_time && currentTime < round.reveal_deadline;
+  const isRevealPhase = round.is_active && currentTime >= round.end_time && currentTime < round.reveal_deadline;
   const canFinalize = round.reveal_deadline > 0 && currentTime >= round.reveal_deadline && !round.finalized;
 
   const formatTime = (timestamp: number): string => {
@@ -48,10 +50,10 @@ const RoundDisplay: React.FC<RoundDisplayProps> = ({ round, walletAddress, onRef
     try {
       // Step 1: Generate seed and commit hash
       const seed = generateSeed();
-      const commitHash = await computeCommitHash(seed, walletAddress, round.round_id);
+      const commitHash = await computeCommitHash(seed, CONFIG.LOTTERY_CONTRACT_ID, walletAddress, round.round_id, round.commit_scheme);
       
-      // Step 2: Store seed for later reveal
-      storeSeed(round.round_id, seed);
+      // Step 2: Store seed for later reveal, under this ticket's commit
+      storeSeed(round.round_id, commitHash, seed);
       
       // Step 3: Approve tokens (if needed)
       const expirationLedger = 2000000; // Far future
@@ -102,43 +104,80 @@ const RoundDisplay: React.FC<RoundDisplayProps> = ({ round, walletAddress, onRef
     setSuccess(null);
 
     try {
-      // Get stored seed
-      const storedSeed = getStoredSeed(round.round_id);
-      
-      if (!storedSeed) {
-        setError('No seed found for this round. You may not have purchased a ticket.');
+      if (revealableTickets.length === 0) {
+        setError('No stored seed found for your tickets in this round. You may not have purchased a ticket.');
         return;
       }
 
-      // Reveal seed
-      const revealXdr = await revealSeed(
-        walletAddress,
-        round.round_id,
-        bytesToHex(storedSeed)
-      );
-
-      // Sign and submit reveal transaction
-      const signedReveal = await signTransactionWithWallet(revealXdr, CONFIG.NETWORK as 'testnet' | 'mainnet');
-      const txHash = await submitTransaction(signedReveal);
-
-      setSuccess(`✅ Seed revealed successfully! Transaction: ${txHash.slice(0, 10)}...`);
+      // Each ticket is revealed in its own transaction, with its own seed
+      let txHash = '';
+      for (const ticket of revealableTickets) {
+        const revealXdr = await revealSeed(
+          walletAddress,
+          round.round_id,
+          ticket.index,
+          ticket.seed
+        );
+
+        // Sign and submit reveal transaction
+        const signedReveal = await signTransactionWithWallet(revealXdr, CONFIG.NETWORK as 'testnet' | 'mainnet');
+        txHash = await submitTransaction(signedReveal);
+
+        // Remove stored seed after successful reveal
+        removeStoredSeed(round.round_id, ticket.commit);
+      }
 
-      // Remove stored seed after successful reveal
-      removeStoredSeed(round.round_id);
+      setSuccess(`✅ ${revealableTickets.length} seed(s) revealed successfully! Last transaction: ${txHash.slice(0, 10)}...`);
 
-      // Refresh round data and balance
+      // Refresh round data, tickets and balance
       setTimeout(() => {
         onRefresh();
+        loadRevealableTickets();
         loadTokenBalance();
       }, 2000);
     } catch (err: any) {
       setError(err.message || 'Failed to reveal seed');
+      loadRevealableTickets();
     } finally {
       setRevealingSeed(false);
     }
   };
 
-  const hasStoredSeed = walletAddress ? getStoredSeed(round.round_id) !== null : false;
+  // Match the seeds stored in this browser to the wallet's unrevealed tickets
+  const loadRevealableTickets = async () => {
+    if (!walletAddress) return;
+
+    const seeds = getStoredSeeds(round.round_id);
+    if (Object.keys(seeds).length === 0) {
+      setRevealableTickets([]);
+      return;
+    }
+
+    setLoadingTickets(true);
+    try {
+      const tickets: RevealableTicket[] = [];
+      for (const index of await getTicketsOf(round.round_id, walletAddress)) {
+        const ticket = await getTicket(round.round_id, index);
+        if (ticket && ticket.seed === null && seeds[ticket.commit]) {
+          tickets.push({ index, commit: ticket.commit, seed: seeds[ticket.commit] });
+        }
+      }
+      setRevealableTickets(tickets);
+    } catch (err) {
+      console.error('Failed to load tickets:', err);
+    } finally {
+      setLoadingTickets(false);
+    }
+  };
+
+  const hasStoredSeed = revealableTickets.length > 0;
+
+  // Load the wallet's tickets once the reveal phase starts
+  useEffect(() => {
+    if (walletAddress && isRevealPhase) {
+      loadRevealableTickets();
+    }
+  }, [walletAddress, isRevealPhase, round.round_id]);
 
   // Load token balance when wallet is connected
   useEffect(() => {
@@ -194,12 +233,14 @@ const RoundDisplay: React.FC<RoundDisplayProps> = ({ round, walletAddress, onRef
           <span className="info-label">Reveal Deadline:</span>
           <span className="info-value">{formatTime(round.reveal_deadline)}</span>
         </div>
-        {round.winner && (
-          <div className="info-item winner">
-            <span className="info-label">Winner:</span>
-            <span className="info-value">{round.winner.slice(0, 8)}...{round.winner.slice(-8)}</span>
+        {round.winners.map((winner, tier) => (
+          <div className="info-item winner" key={tier}>
+            <span className="info-label">{round.winners.length > 1 ? `Tier ${tier + 1} Winner:` : 'Winner:'}</span>
+            <span className="info-value">
+              {winner.slice(0, 8)}...{winner.slice(-8)} (ticket #{round.winning_tickets[tier]})
+            </span>
           </div>
-        )}
+        ))}
         <div className="info-item">
           <span className="info-label">Allow Multiple:</span>
           <span className="info-value">{round.allow_multiple ? 'Yes' : 'No'}</span>
@@ -249,13 +290,13 @@ const RoundDisplay: React.FC<RoundDisplayProps> = ({ round, walletAddress, onRef
               disabled={revealingSeed}
               className="action-button reveal-button"
             >
-              {revealingSeed ? 'Preparing...' : 'Reveal Seed'}
+              {revealingSeed ? 'Preparing...' : revealableTickets.length > 1 ? `Reveal ${revealableTickets.length} Seeds` : 'Reveal Seed'}
             </button>
-            <CLIInstructions round={round} walletAddress={walletAddress} operation="reveal_seed" />
+            <CLIInstructions round={round} walletAddress={walletAddress} operation="reveal_seed" tickets={revealableTickets} />
           </>
         )}
 
-        {isRevealPhase && walletAddress && !hasStoredSeed && (
+        {isRevealPhase && walletAddress && !hasStoredSeed && !loadingTickets && (
           <div className="info-message">
             No stored seed found. You may not have purchased a ticket for this round.
           </div>
@@ -3,12 +3,17 @@
  * 
  * The commit-reveal scheme works as follows:
  * 1. Generate a random seed
- * 2. Compute: commit_hash = SHA256(seed || participant_address || round_id)
- * 3. Store the seed locally for later reveal
+ * 2. Compute the commit hash under the round's commit scheme:
+ *    V2: SHA256("lottery-commit-v2" || contract_address_xdr || round_id || participant_address_xdr || seed)
+ *    V1: SHA256(seed || participant_address || round_id), rounds created before the upgrade
+ * 3. Store the seed locally, keyed by its commit hash, for later reveal
  * 4. Use commit_hash when buying ticket
- * 5. After round ends, reveal the seed to verify
+ * 5. After round ends, reveal each ticket's seed with its ticket index
  */
 
+import { Address } from '@stellar/stellar-sdk';
+import type { CommitScheme } from './contract';
+
 /**
  * Generate a random 32-byte seed
  */
@@ -27,29 +32,65 @@ const sha256 = async (data: Uint8Array): Promise<Uint8Array> => {
 };
 
 /**
- * Compute commit hash: SHA256(seed || address || round_id)
+ * Encode round_id as 8 bytes, big-endian
+ */
+const roundIdToBytes = (roundId: number): Uint8Array => {
+  const roundIdBytes = new Uint8Array(8);
+  const view = new DataView(roundIdBytes.buffer);
+  view.setBigUint64(0, BigInt(roundId), false); // big-endian
+  return roundIdBytes;
+};
+
+/**
+ * Concatenate byte arrays
+ */
+const concatBytes = (...parts: Uint8Array[]): Uint8Array => {
+  const combined = new Uint8Array(parts.reduce((length, part) => length + part.length, 0));
+  let offset = 0;
+  for (const part of parts) {
+    combined.set(part, offset);
+    offset += part.length;
+  }
+  return combined;
+};
+
+/**
+ * Domain separator of the V2 commit scheme
+ */
+const COMMIT_DOMAIN = new TextEncoder().encode('lottery-commit-v2');
+
+/**
+ * Compute commit hash for a round:
+ * SHA256("lottery-commit-v2" || contract_address_xdr || round_id || participant_address_xdr || seed)
+ * Addresses are encoded as ScVal XDR, exactly as the contract's to_xdr does.
  */
 export const computeCommitHash = async (
+  seed: Uint8Array,
+  contractId: string,
+  participantAddress: string,
+  roundId: number,
+  scheme: CommitScheme = 'V2'
+): Promise<Uint8Array> => {
+  if (scheme === 'V1') {
+    return computeCommitHashV1(seed, participantAddress, roundId);
+  }
+  
+  const contractBytes = new Uint8Array(new Address(contractId).toScVal().toXDR());
+  const addressBytes = new Uint8Array(new Address(participantAddress).toScVal().toXDR());
+  return await sha256(concatBytes(COMMIT_DOMAIN, contractBytes, roundIdToBytes(roundId), addressBytes, seed));
+};
+
+/**
+ * Compute a V1 commit hash, for rounds created before the V2 scheme:
+ * SHA256(seed || address || round_id), with the address as its UTF-8 strkey
+ */
+export const computeCommitHashV1 = async (
   seed: Uint8Array,
   participantAddress: string,
   roundId: number
 ): Promise<Uint8Array> => {
-  // Convert address to bytes
   const addressBytes = new TextEncoder().encode(participantAddress);
-  
-  // Convert round_id to 8-byte big-endian
-  const roundIdBytes = new Uint8Array(8);
-  const view = new DataView(roundIdBytes.buffer);
-  view.setBigUint64(0, BigInt(roundId), false); // big-endian
-  
-  // Concatenate: seed || address || round_id
-  const combined = new Uint8Array(seed.length + addressBytes.length + roundIdBytes.length);
-  combined.set(seed, 0);
-  combined.set(addressBytes, seed.length);
-  combined.set(roundIdBytes, seed.length + addressBytes.length);
-  
-  // Compute SHA256
-  return await sha256(combined);
+  return await sha256(concatBytes(seed, addressBytes, roundIdToBytes(roundId)));
 };
 
 /**
@@ -73,30 +114,46 @@ export const hexToBytes = (hex: string): Uint8Array => {
 };
 
 /**
- * Store seed in localStorage for later reveal
+ * A ticket of the connected wallet whose seed is stored and not yet revealed
  */
-export const storeSeed = (roundId: number, seed: Uint8Array): void => {
-  const key = `lottery_seed_${roundId}`;
-  const hexSeed = bytesToHex(seed);
-  localStorage.setItem(key, hexSeed);
+export interface RevealableTicket {
+  index: number;
+  commit: string; // hex
+  seed: string; // hex
+}
+
+/**
+ * localStorage key of a round's stored seeds
+ */
+const seedsKey = (roundId: number): string => `lottery_seeds_${roundId}`;
+
+/**
+ * Store a ticket's seed in localStorage for later reveal. Seeds are keyed by
+ * the ticket's commit hash, so each ticket bought in a round keeps its own.
+ */
+export const storeSeed = (roundId: number, commitHash: Uint8Array, seed: Uint8Array): void => {
+  const seeds = getStoredSeeds(roundId);
+  seeds[bytesToHex(commitHash)] = bytesToHex(seed);
+  localStorage.setItem(seedsKey(roundId), JSON.stringify(seeds));
 };
 
 /**
- * Retrieve seed from localStorage
+ * Retrieve the seeds stored for a round, mapping each commit hash to its seed, both hex
  */
-export const getStoredSeed = (roundId: number): Uint8Array | null => {
-  const key = `lottery_seed_${roundId}`;
-  const hexSeed = localStorage.getItem(key);
-  if (!hexSeed) {
-    return null;
-  }
-  return hexToBytes(hexSeed);
+export const getStoredSeeds = (roundId: number): Record<string, string> => {
+  const stored = localStorage.getItem(seedsKey(roundId));
+  return stored ? JSON.parse(stored) : {};
 };
 
 /**
- * Remove stored seed after reveal
+ * Remove a ticket's stored seed after reveal
  */
-export const removeStoredSeed = (roundId: number): void => {
-  const key = `lottery_seed_${roundId}`;
-  localStorage.removeItem(key);
+export const removeStoredSeed = (roundId: number, commitHash: string): void => {
+  const seeds = getStoredSeeds(roundId);
+  delete seeds[commitHash];
+  if (Object.keys(seeds).length === 0) {
+    localStorage.removeItem(seedsKey(roundId));
+  } else {
+    localStorage.setItem(seedsKey(roundId), JSON.stringify(seeds));
+  }
 };
@@ -1,6 +1,7 @@
 import { CONFIG, getNetworkPassphrase, getRpcUrl } from '../config';
 import { Server, xdr, Keypair } from 'soroban-client';
 import { nativeToScVal, scValToNative, Horizon as StellarHorizon, Account, TransactionBuilder, Contract } from '@stellar/stellar-sdk';
+import { bytesToHex } from './commitReveal';
 
 // Contract interface types
 export interface LotteryRound {
@@ -9,13 +10,29 @@ export interface LotteryRound {
   total_pool: bigint;
   participants_count: number;
   is_active: boolean;
-  winner: string | null;
+  winners: string[]; // one per prize tier, 1st place first; empty until drawn
+  winning_tickets: number[]; // ticket index behind each of winners
   end_time: number;
   reveal_deadline: number;
   finalized: boolean;
   allow_multiple: boolean;
+  commit_scheme: CommitScheme;
 }
 
+// How a round's commit hashes are computed, see utils/commitReveal
+export type CommitScheme = 'V1' | 'V2';
+
+// A single ticket as returned by get_ticket
+export interface Ticket {
+  index: number;
+  owner: string;
+  commit: string; // hex
+  seed: string | null; // hex, null until revealed
+}
+
+// Most entries the contract returns from one page of a paginated view
+const MAX_PAGE_SIZE = 100;
+
 /**
  * Get RPC server instance
  */
@@ -177,11 +194,14 @@ function formatRoundData(roundObj: any): LotteryRound {
     total_pool: BigInt(String(isArray ? roundData[2] : roundData.total_pool || 0)),
     participants_count: Number(isArray ? roundData[3] : roundData.participants_count || 0),
     is_active: isArray ? Boolean(roundData[4]) : (roundData.is_active !== undefined ? Boolean(roundData.is_active) : false),
-    winner: isArray ? (roundData[5] ? String(roundData[5]) : null) : (roundData.winner ? String(roundData.winner) : null),
+    winners: (roundData.winners || []).map((winner: any) => String(winner)),
+    winning_tickets: (roundData.winning_tickets || []).map((ticket: any) => Number(ticket)),
     end_time: Number(isArray ? roundData[6] : roundData.end_time || 0),
     reveal_deadline: Number(isArray ? roundData[7] : roundData.reveal_deadline || 0),
     finalized: isArray ? Boolean(roundData[8]) : (roundData.finalized !== undefined ? Boolean(roundData.finalized) : false),
     allow_multiple: isArray ? Boolean(roundData[9]) : (roundData.allow_multiple !== undefined ? Boolean(roundData.allow_multiple) : false),
+    // Unit enum variants decode as a one-element array, e.g. ['V1']
+    commit_scheme: [roundData.commit_scheme].flat()[0] === 'V1' ? 'V1' : 'V2',
   };
 }
 
@@ -314,11 +334,12 @@ export const buyTicket = async (
 };
 
 /**
- * Reveal seed
+ * Reveal the seed committed for one ticket
  */
 export const revealSeed = async (
   userAddress: string,
   roundId: number,
+  ticketIndex: number,
   seed: string // hex string
 ): Promise<string> => {
   try {
@@ -352,6 +373,7 @@ export const revealSeed = async (
           'reveal_seed',
           nativeToScVal(roundId, { type: 'u64' }),
           nativeToScVal(userAddress, { type: 'address' }),
+          nativeToScVal(ticketIndex, { type: 'u64' }),
           seedScVal
         ) as any
       )
@@ -383,6 +405,102 @@ export const revealSeed = async (
   }
 };
 
+/**
+ * Simulate a read-only call to the lottery contract and return its decoded
+ * result, or null if the call fails
+ */
+const readContract = async (method: string, ...args: xdr.ScVal[]): Promise<any> => {
+  const server = getRpcServer();
+  const contract = getContract();
+
+  // Create a random source account for read operations
+  const sourceKeypair = Keypair.random();
+  const sourceAccount = new Account(sourceKeypair.publicKey(), '0');
+
+  const transaction = new TransactionBuilder(sourceAccount, {
+    fee: '100',
+    networkPassphrase: getNetworkPassphrase(),
+  })
+    .addOperation(contract.call(method, ...(args as any[])) as any)
+    .setTimeout(60)
+    .build();
+
+  // Convert @stellar/stellar-sdk transaction to soroban-client format
+  const txXdrForRead = transaction.toXDR();
+  const sorobanTxForRead = xdr.Transaction.fromXDR(txXdrForRead, 'base64') as any;
+
+  const simResult = await server.simulateTransaction(sorobanTxForRead);
+
+  if ('error' in simResult || (simResult as any).error) {
+    return null;
+  }
+
+  if (!simResult.results || !simResult.results[0]) {
+    return null;
+  }
+
+  const result = simResult.results[0];
+  if (!result || !('retval' in result) || !result.retval) {
+    return null;
+  }
+
+  return scValToNative(result.retval as any);
+};
+
+/**
+ * List the indexes of every ticket an address holds in a round, in purchase order
+ */
+export const getTicketsOf = async (roundId: number, address: string): Promise<number[]> => {
+  try {
+    const indexes: number[] = [];
+    for (;;) {
+      const page = await readContract(
+        'tickets_of',
+        nativeToScVal(roundId, { type: 'u64' }),
+        nativeToScVal(address, { type: 'address' }),
+        nativeToScVal(indexes.length, { type: 'u64' }),
+        nativeToScVal(MAX_PAGE_SIZE, { type: 'u32' })
+      );
+      if (!page) {
+        break;
+      }
+      indexes.push(...page.map((index: any) => Number(index)));
+      if (page.length < MAX_PAGE_SIZE) {
+        break;
+      }
+    }
+    return indexes;
+  } catch (error: any) {
+    console.error("Error listing tickets:", error);
+    return [];
+  }
+};
+
+/**
+ * View a ticket's owner, commit and revealed seed, or null if it was never sold
+ */
+export const getTicket = async (roundId: number, index: number): Promise<Ticket | null> => {
+  try {
+    const ticket = await readContract(
+      'get_ticket',
+      nativeToScVal(roundId, { type: 'u64' }),
+      nativeToScVal(index, { type: 'u64' })
+    );
+    if (!ticket) {
+      return null;
+    }
+    return {
+      index: Number(ticket.index),
+      owner: String(ticket.owner),
+      commit: bytesToHex(new Uint8Array(ticket.commit)),
+      seed: ticket.seed ? bytesToHex(new Uint8Array(ticket.seed)) : null,
+    };
+  } catch (error: any) {
+    console.error("Error viewing ticket:", error);
+    return null;
+  }
+};
+
 /**
  * Check token balance
  */
@@ -0,0 +1,70 @@
+#!/usr/bin/env bash
+# Rebuild the old contract builds the upgrade tests load from
+# contracts/hello-world/test_wasms, each from the tag listed for it in
+# test_wasms/SOURCES, and check them against test_wasms/SHA256SUMS.
+#
+#   tools/build_test_wasms.sh            rebuild and compare with SHA256SUMS
+#   tools/build_test_wasms.sh --update   rebuild, replace the builds and rewrite SHA256SUMS
+#
+# A build is only byte-for-byte reproducible with the same rustc release and
+# the same dependency versions. SOURCES names the rustc release the builds
+# were made with, and each build is locked to the <name>.Cargo.lock kept next
+# to it. SOURCES also names the commit each tag must point at, so a moved tag
+# is caught before anything is built. Each commit is checked out at the same
+# fixed path and the cargo home is remapped, since both paths end up in the WASM.
+set -euo pipefail
+
+update=false
+if [ "${1:-}" = "--update" ]; then
+    update=true
+fi
+
+repo=$(git rev-parse --show-toplevel)
+wasm_dir="$repo/contracts/hello-world/test_wasms"
+build_dir=/tmp/lottery-test-wasms
+cargo_home=${CARGO_HOME:-$HOME/.cargo}
+
+expected_rustc=$(sed -n 's/^# rustc //p' "$wasm_dir/SOURCES")
+actual_rustc=$(rustc --version | cut -d' ' -f2)
+if [ "$expected_rustc" != "$actual_rustc" ]; then
+    echo "warning: the builds were made with rustc $expected_rustc, this is rustc $actual_rustc, so hashes will likely differ" >&2
+fi
+
+rm -rf "$build_dir"
+mkdir -p "$build_dir/out"
+trap 'git -C "$repo" worktree remove --force "$build_dir/src" 2>/dev/null || true' EXIT
+
+grep -v '^#' "$wasm_dir/SOURCES" | while read -r name tag commit; do
+    if [ -z "$name" ]; then
+        continue
+    fi
+    tagged=$(git -C "$repo" rev-parse --verify --quiet "refs/tags/$tag^{commit}" || true)
+    if [ -z "$tagged" ]; then
+        echo "error: tag $tag is missing, fetch it with: git fetch origin tag $tag" >&2
+        exit 1
+    fi
+    if [ "$tagged" != "$commit" ]; then
+        echo "error: tag $tag points at $tagged, SOURCES expects $commit" >&2
+        exit 1
+    fi
+    echo "Building $name from $tag ($commit)"
+    git -C "$repo" worktree add --quiet --detach "$build_dir/src" "$commit"
+    cp "$wasm_dir/${name%.wasm}.Cargo.lock" "$build_dir/src/Cargo.lock"
+    (
+        cd "$build_dir/src"
+        RUSTFLAGS="--remap-path-prefix=$cargo_home=/cargo" CARGO_TARGET_DIR="$build_dir/target" \
+            cargo build --quiet --locked --release --target wasm32v1-none -p hello-world
+    )
+    cp "$build_dir/target/wasm32v1-none/release/hello_world.wasm" "$build_dir/out/$name"
+    git -C "$repo" worktree remove --force "$build_dir/src"
+done
+
+cd "$build_dir/out"
+if $update; then
+    cp ./*.wasm "$wasm_dir/"
+    sha256sum ./*.wasm | sed 's|  \./|  |' > "$wasm_dir/SHA256SUMS"
+    echo "Updated $wasm_dir"
+    cat "$wasm_dir/SHA256SUMS"
+else
+    sha256sum -c "$wasm_dir/SHA256SUMS"
+fi
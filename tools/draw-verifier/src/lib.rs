@@ -0,0 +1,201 @@
+// Off-chain verifier for lottery draws. Given the DrawRecord a round stores
+// at finalization (read it with the contract's get_draw view) and the round's
+// reveals (get_reveals, with each seed from get_ticket), it recomputes the
+// seed chain, the final hash and the winning tickets exactly as the contract
+// does, using only the standard library.
+
+mod sha256;
+pub use sha256::sha256;
+
+// Where a round's draw got its randomness, mirroring the contract's EntropyMode
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EntropyMode {
+    CommitReveal,
+    CommitRevealPrng,
+    Prng,
+    Provider,
+}
+
+// A round's draw transcript, field for field as returned by get_draw
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DrawRecord {
+    pub entropy_mode: EntropyMode,
+    pub combined_hash: [u8; 32],
+    pub ledger_sequence: Option<u32>,
+    pub prng_output: Option<[u8; 32]>, // the provider's value in Provider rounds
+    pub final_hash: [u8; 32],
+    pub reveal_count: u64,
+    pub eligible_count: u64,
+    pub revealed_only: bool,
+    pub winning_tickets: Vec<u64>,
+}
+
+// One revealed ticket and its seed, listed in the order get_reveals returns them
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Reveal {
+    pub ticket_index: u64,
+    pub seed: Vec<u8>,
+}
+
+// The first point where a record disagrees with the recomputed draw
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum VerifyError {
+    RevealCountMismatch,
+    InputsDoNotMatchMode,
+    CombinedHashMismatch { computed: [u8; 32] },
+    FinalHashMismatch { computed: [u8; 32] },
+    NotEnoughEligibleTickets,
+    WinnersMismatch { computed: Vec<u64> },
+}
+
+// sha256(acc || input)
+pub fn chain(acc: &[u8; 32], input: &[u8]) -> [u8; 32] {
+    let mut hash_input = acc.to_vec();
+    hash_input.extend_from_slice(input);
+    sha256(&hash_input)
+}
+
+// Combine revealed seeds in reveal order: h = sha256(h || seed), from 32 zero bytes
+pub fn combine_seeds(seeds: &[Vec<u8>]) -> [u8; 32] {
+    seeds.iter().fold([0u8; 32], |combined, seed| chain(&combined, seed))
+}
+
+// Final hash from the inputs the round's entropy mode uses
+pub fn final_hash(seed_hash: &[u8; 32], ledger_sequence: Option<u32>, prng_output: Option<&[u8; 32]>) -> [u8; 32] {
+    let mut hash = *seed_hash;
+    if let Some(sequence) = ledger_sequence {
+        hash = chain(&hash, &sequence.to_be_bytes());
+    }
+    match (ledger_sequence, prng_output) {
+        (Some(_), Some(prng_output)) => chain(&hash, prng_output),
+        (None, Some(prng_output)) => *prng_output,
+        (_, None) => hash,
+    }
+}
+
+// Hash for draw n: the final hash for the first draw, sha256(final_hash || n) after it
+pub fn draw_hash(final_hash: &[u8; 32], draw: u32) -> [u8; 32] {
+    if draw == 0 {
+        *final_hash
+    } else {
+        chain(final_hash, &draw.to_be_bytes())
+    }
+}
+
+// Map a 64-bit word to [0, bound) without modulo bias, or None if it falls
+// in the short tail below 2^64 mod bound
+pub fn reduce(word: u64, bound: u64) -> Option<u64> {
+    let threshold = bound.wrapping_neg() % bound;
+    if word >= threshold {
+        Some(word % bound)
+    } else {
+        None
+    }
+}
+
+// Uniform index in [0, bound) by rejection sampling over the hash's four
+// big-endian words, rehashing with sha256(hash || n) on the n-th retry
+pub fn uniform_index(hash: &[u8; 32], bound: u64) -> u64 {
+    assert!(bound > 0, "bound must be positive");
+
+    let mut hash = *hash;
+    let mut extension = 0u32;
+    loop {
+        for chunk in hash.chunks_exact(8) {
+            let word = u64::from_be_bytes(chunk.try_into().unwrap());
+            if let Some(index) = reduce(word, bound) {
+                return index;
+            }
+        }
+        extension += 1;
+        hash = chain(&hash, &extension.to_be_bytes());
+    }
+}
+
+// Draw `count` distinct slots out of 1..=eligible, in draw order
+pub fn draw_slots(final_hash: &[u8; 32], eligible: u64, count: u32) -> Vec<u64> {
+    let mut slots = Vec::new();
+    let mut drawn_sorted: Vec<u64> = Vec::new();
+    for draw in 0..count {
+        let remaining = eligible - draw as u64;
+        let mut slot = uniform_index(&draw_hash(final_hash, draw), remaining) + 1;
+
+        // Step over slots that already won, keeping the list sorted
+        let mut insert_at = drawn_sorted.len();
+        for (position, &drawn) in drawn_sorted.iter().enumerate() {
+            if drawn <= slot {
+                slot += 1;
+            } else {
+                insert_at = position;
+                break;
+            }
+        }
+        drawn_sorted.insert(insert_at, slot);
+        slots.push(slot);
+    }
+    slots
+}
+
+// Recompute a draw from its record and the round's reveals. Returns the
+// winning tickets, 1st place first, or the first field that does not match.
+pub fn verify(record: &DrawRecord, reveals: &[Reveal]) -> Result<Vec<u64>, VerifyError> {
+    if reveals.len() as u64 != record.reveal_count {
+        return Err(VerifyError::RevealCountMismatch);
+    }
+
+    let expects_sequence = matches!(record.entropy_mode, EntropyMode::CommitReveal | EntropyMode::CommitRevealPrng);
+    let expects_prng = record.entropy_mode != EntropyMode::CommitReveal;
+    if record.ledger_sequence.is_some() != expects_sequence || record.prng_output.is_some() != expects_prng {
+        return Err(VerifyError::InputsDoNotMatchMode);
+    }
+
+    let seeds: Vec<Vec<u8>> = reveals.iter().map(|reveal| reveal.seed.clone()).collect();
+    let combined = combine_seeds(&seeds);
+    if combined != record.combined_hash {
+        return Err(VerifyError::CombinedHashMismatch { computed: combined });
+    }
+
+    let computed_final = final_hash(&combined, record.ledger_sequence, record.prng_output.as_ref());
+    if computed_final != record.final_hash {
+        return Err(VerifyError::FinalHashMismatch { computed: computed_final });
+    }
+
+    let count = record.winning_tickets.len() as u32;
+    let eligible = if record.revealed_only {
+        reveals.len() as u64
+    } else {
+        record.eligible_count
+    };
+    if eligible != record.eligible_count || eligible < count as u64 {
+        return Err(VerifyError::NotEnoughEligibleTickets);
+    }
+
+    let winners: Vec<u64> = draw_slots(&computed_final, eligible, count)
+        .into_iter()
+        .map(|slot| if record.revealed_only { reveals[slot as usize - 1].ticket_index } else { slot })
+        .collect();
+    if winners != record.winning_tickets {
+        return Err(VerifyError::WinnersMismatch { computed: winners });
+    }
+    Ok(winners)
+}
+
+// Parse a 32-byte hash from the hex the Stellar CLI prints for BytesN<32>
+pub fn parse_hash(hex: &str) -> Option<[u8; 32]> {
+    parse_hex(hex)?.try_into().ok()
+}
+
+// Parse hex, as printed for Bytes values such as revealed seeds
+pub fn parse_hex(hex: &str) -> Option<Vec<u8>> {
+    let hex = hex.trim().trim_start_matches("0x");
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod test;
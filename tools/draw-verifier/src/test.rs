@@ -0,0 +1,125 @@
+#![cfg(test)]
+
+use super::*;
+
+// Helper function to build a small, internally consistent record and the reveals behind it
+fn sample_record(entropy_mode: EntropyMode, revealed_only: bool) -> (DrawRecord, Vec<Reveal>) {
+    let reveals = vec![
+        Reveal { ticket_index: 3, seed: b"carol".to_vec() },
+        Reveal { ticket_index: 1, seed: b"alice".to_vec() },
+    ];
+    let seeds: Vec<Vec<u8>> = reveals.iter().map(|reveal| reveal.seed.clone()).collect();
+    let combined_hash = combine_seeds(&seeds);
+    let ledger_sequence = matches!(entropy_mode, EntropyMode::CommitReveal | EntropyMode::CommitRevealPrng).then_some(42);
+    let prng_output = (entropy_mode != EntropyMode::CommitReveal).then_some([7u8; 32]);
+    let final_hash = final_hash(&combined_hash, ledger_sequence, prng_output.as_ref());
+    let eligible_count = if revealed_only { 2 } else { 4 };
+    let slots = draw_slots(&final_hash, eligible_count, 2);
+    let winning_tickets = slots
+        .into_iter()
+        .map(|slot| if revealed_only { reveals[slot as usize - 1].ticket_index } else { slot })
+        .collect();
+    let record = DrawRecord {
+        entropy_mode,
+        combined_hash,
+        ledger_sequence,
+        prng_output,
+        final_hash,
+        reveal_count: 2,
+        eligible_count,
+        revealed_only,
+        winning_tickets,
+    };
+    (record, reveals)
+}
+
+#[test]
+fn test_sha256_vectors() {
+    assert_eq!(
+        parse_hash("e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"),
+        Some(sha256(b""))
+    );
+    assert_eq!(
+        parse_hash("ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"),
+        Some(sha256(b"abc"))
+    );
+    // 56 bytes, so the length spills into a second block
+    assert_eq!(
+        parse_hash("248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1"),
+        Some(sha256(b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq"))
+    );
+}
+
+#[test]
+fn test_reduce_and_uniform_index() {
+    // 2^64 mod 3 = 1, so only word 0 is rejected
+    assert_eq!(reduce(0, 3), None);
+    assert_eq!(reduce(1, 3), Some(1));
+    assert_eq!(reduce(u64::MAX, 3), Some(0));
+
+    // A hash of all zero words is rejected outright and rehashed
+    let index = uniform_index(&[0u8; 32], 3);
+    assert_eq!(index, uniform_index(&chain(&[0u8; 32], &1u32.to_be_bytes()), 3));
+}
+
+#[test]
+fn test_draw_slots_are_distinct() {
+    let slots = draw_slots(&sha256(b"round"), 5, 5);
+    let mut sorted = slots.clone();
+    sorted.sort();
+    assert_eq!(sorted, vec![1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_verify_every_mode() {
+    for mode in [EntropyMode::CommitReveal, EntropyMode::CommitRevealPrng, EntropyMode::Prng, EntropyMode::Provider] {
+        for revealed_only in [false, true] {
+            let (record, reveals) = sample_record(mode, revealed_only);
+            assert_eq!(verify(&record, &reveals), Ok(record.winning_tickets.clone()));
+        }
+    }
+}
+
+#[test]
+fn test_verify_detects_tampering() {
+    let (record, reveals) = sample_record(EntropyMode::CommitReveal, true);
+
+    let mut tampered = reveals.clone();
+    tampered[0].seed = b"mallory".to_vec();
+    assert!(matches!(verify(&record, &tampered), Err(VerifyError::CombinedHashMismatch { .. })));
+
+    // Reveals listed out of order chain to a different hash
+    let mut tampered = reveals.clone();
+    tampered.reverse();
+    assert!(matches!(verify(&record, &tampered), Err(VerifyError::CombinedHashMismatch { .. })));
+
+    assert_eq!(verify(&record, &reveals[..1]), Err(VerifyError::RevealCountMismatch));
+
+    let mut tampered = record.clone();
+    tampered.ledger_sequence = Some(43);
+    assert!(matches!(verify(&tampered, &reveals), Err(VerifyError::FinalHashMismatch { .. })));
+
+    let mut tampered = record.clone();
+    tampered.prng_output = Some([7u8; 32]);
+    assert_eq!(verify(&tampered, &reveals), Err(VerifyError::InputsDoNotMatchMode));
+
+    let mut tampered = record.clone();
+    tampered.reveal_count = 3;
+    assert_eq!(verify(&tampered, &reveals), Err(VerifyError::RevealCountMismatch));
+
+    let mut tampered = record.clone();
+    tampered.eligible_count = 4;
+    assert_eq!(verify(&tampered, &reveals), Err(VerifyError::NotEnoughEligibleTickets));
+
+    let mut tampered = record.clone();
+    tampered.winning_tickets.reverse();
+    assert_eq!(verify(&tampered, &reveals), Err(VerifyError::WinnersMismatch { computed: record.winning_tickets.clone() }));
+}
+
+#[test]
+fn test_parse_hex() {
+    assert_eq!(parse_hex("0x00ff10"), Some(vec![0, 255, 16]));
+    assert_eq!(parse_hex("abc"), None);
+    assert_eq!(parse_hex("zz"), None);
+    assert_eq!(parse_hash("00"), None);
+}